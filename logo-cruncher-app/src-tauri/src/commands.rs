@@ -1,13 +1,14 @@
 use logoLoader::{test, Config, Jobs};
 
 #[tauri::command]
-pub fn process_json(json: &str) -> Jobs {
+pub async fn process_json(json: String) -> Jobs {
     let config = Config::get();
-    let logos = Jobs::load_json_job(json, config.job(), &config.temp_job_file(), false)
+    let logos = Jobs::load_json_job(&json, &config, false)
+        .await
         .expect("Failed to load JSON job");
     println!("Распарсили заданий {}", logos.logos.len());
     // println!("Привет от Json из Rust2! {json} {:?}", logos);
-    let result = test(json);
+    let result = test(&json);
     println!("Привет от Json из Rust2 process_json! {result} dd");
     logos
 }
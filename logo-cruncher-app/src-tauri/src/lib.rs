@@ -1,8 +1,8 @@
 use commands::process_json;
-use logoLoader::{test, Config, Jobs, LogoJob};
-use serde::de::Unexpected::Option;
+use logoLoader::{otp_qr_png_base64, test, Config, Jobs, LogoJob, ServerSession};
 use std::fs;
-use tauri::{AppHandle, Emitter};
+use tauri::async_runtime::Mutex;
+use tauri::{AppHandle, Emitter, State};
 use tauri_plugin_dialog::DialogExt;
 
 mod commands;
@@ -15,16 +15,59 @@ fn greet(app: AppHandle, name: &str) -> String {
     res
 }
 
+/// Сессия на сервере между вводом пароля и одноразового кода
+#[derive(Default)]
+struct LoginState(Mutex<Option<ServerSession>>);
+
+/// Первый шаг входа: логин и пароль. QR код для подключения аутентификатора
+/// отправляем в GUI картинкой до запроса одноразового кода.
+/// Возвращает, нужен ли одноразовый код
 #[tauri::command]
-async fn getLogos(app: AppHandle, code: usize) {
-    println!("Загрузка заданий с сервера по коду {}", code);
+async fn start_login(
+    app: AppHandle,
+    state: State<'_, LoginState>,
+    login: String,
+    password: String,
+) -> Result<bool, String> {
+    let session = ServerSession::login(&login, &password)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(otp_url) = session.otp_url() {
+        let png = otp_qr_png_base64(&otp_url).map_err(|e| e.to_string())?;
+        app.emit("event-otp-qr", format!("data:image/png;base64,{png}"))
+            .map_err(|e| e.to_string())?;
+    }
+
+    let otp_required = session.otp_required();
+    *state.0.lock().await = Some(session);
+    Ok(otp_required)
+}
+
+/// Второй шаг входа: одноразовый код, затем загрузка заданий с сервера.
+/// После ошибки кода вход начинается заново
+#[tauri::command]
+async fn getLogos(state: State<'_, LoginState>, code: Option<String>) -> Result<Jobs, String> {
+    let session = state
+        .0
+        .lock()
+        .await
+        .take()
+        .ok_or("Сначала выполните вход")?;
+
+    if session.otp_required() {
+        let code = code.ok_or("Введите одноразовый код")?;
+        println!("Загрузка заданий с сервера по коду");
+        session.submit_otp(&code).await.map_err(|e| e.to_string())?;
+    }
 
-    let logos = Jobs::load_from_server("", "", Some(code.to_string()))
+    let config = Config::get();
+    let logos = session
+        .load_jobs(&config)
         .await
-        .unwrap();
+        .map_err(|e| e.to_string())?;
     println!("Результаты {:?}", logos);
-    // let res = format!("Загрузка заданий с сервера по коду", code);
-    // app.emit("event-greet-finished", &res).unwrap();
+    Ok(logos)
 }
 
 #[tauri::command]
@@ -64,8 +107,10 @@ pub fn run() {
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
+        .manage(LoginState::default())
         .invoke_handler(tauri::generate_handler![
             greet,
+            start_login,
             getLogos,
             get_file_list,
             commands::process_json
@@ -7,6 +7,8 @@ import {load, Store} from '@tauri-apps/plugin-store';
 interface LogoJob {
     id: number;
     url: string;
+    app_icon: boolean;
+    fallback_urls?: string[];
 };
 
 interface Jobs {
@@ -45,9 +47,12 @@ export class AppComponent implements OnInit, OnDestroy {
     fileList: string[] = [];
     imageUrls: string[] = [];
     jsonText: string = "";
-    logos: LogoJob[] = [{id: 34293493, url: "Url 1"}, {id: 342233, url: "Url 2"}];
+    logos: LogoJob[] = [{id: 34293493, url: "Url 1", app_icon: false}, {id: 342233, url: "Url 2", app_icon: false}];
     angularVersion = VERSION.full;
     subscription: Promise<UnlistenFn> | undefined;
+    otpQrSubscription: Promise<UnlistenFn> | undefined;
+    otpQr: string = "";
+    otpRequired = false;
     store: Store | undefined;
 
     async ngOnInit() {
@@ -57,6 +62,9 @@ export class AppComponent implements OnInit, OnDestroy {
                 `Emmit ${event.payload} ${event}`
             );
         });
+        this.otpQrSubscription = listen<string>('event-otp-qr', (event) => {
+            this.otpQr = event.payload;
+        });
     }
 
     async ngOnDestroy() {
@@ -64,6 +72,10 @@ export class AppComponent implements OnInit, OnDestroy {
             const unlisten = await this.subscription;
             unlisten();
         }
+        if (this.otpQrSubscription) {
+            const unlisten = await this.otpQrSubscription;
+            unlisten();
+        }
         await this.store?.save();
     }
 
@@ -138,11 +150,34 @@ export class AppComponent implements OnInit, OnDestroy {
         // });
     }
 
-    getAccess(code: number): void {
-        console.log("Доступ", code);
+    // Первый шаг: логин и пароль, QR код приходит событием event-otp-qr
+    login(login: string, password: string): void {
+        this.otpQr = "";
+        invoke<boolean>("start_login", {login, password}).then((otpRequired) => {
+            this.otpRequired = otpRequired;
+            this.greetingMessage = otpRequired ? "Введите одноразовый код" : "Вход выполнен";
+            if (!otpRequired) {
+                this.getAccess("");
+            }
+        }).catch((err) => {
+            console.error(err);
+            this.greetingMessage = "Ошибка: " + String(err);
+        });
+    }
+
+    // Второй шаг: одноразовый код и загрузка заявок
+    getAccess(code: string): void {
+        console.log("Доступ");
 
-        invoke<string>("getLogos", {code}).then(async () => {
+        invoke<Jobs>("getLogos", {code: code || null}).then((jobs: Jobs) => {
+            this.logos = jobs.logos;
+            this.otpRequired = false;
+            this.otpQr = "";
             console.log("getLogos Done");
+        }).catch((err) => {
+            console.error(err);
+            this.otpRequired = false;
+            this.greetingMessage = "Ошибка: " + String(err);
         });
     }
 
@@ -0,0 +1,92 @@
+use crate::page_extractors::{google_image_size, yandex_avatar_size, YANDEX_MAX_SIZE};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use url::Url;
+
+// `=s0` у googleusercontent отдаёт картинку в исходном размере
+const GOOGLE_ORIGINAL_SIZE: u32 = 0;
+// Наибольший размер аватара t.me/i/userpic
+const TELEGRAM_USERPIC_SIZE: &str = "320";
+// Параметры размера картинки в ссылках CDN
+const SIZE_PARAMS: &[&str] = &["size", "width", "height", "w", "h", "resize"];
+// Параметры обрезки аватаров VK
+const VK_SIZE_PARAMS: &[&str] = &["size", "crop", "ava"];
+// Параметры подписи ссылки: подписанная ссылка (sun*.userapi.com/s/v1/...?sign=...,
+// S3 и т.п.) с изменённым путём или параметрами отклоняется сервером
+const SIGNATURE_PARAMS: &[&str] = &["sign", "sig", "signature", "x-amz-signature"];
+
+// Миниатюра WordPress и похожих CMS: logo-150x150.png
+static THUMBNAIL_SUFFIX_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)-\d{2,4}x\d{2,4}(\.(?:png|jpe?g|gif|webp))$").unwrap());
+// Преобразования Cloudinary: /image/upload/w_64,h_64,c_fill/v123/logo.png
+static CLOUDINARY_TRANSFORM_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(/image/upload/)(?:[a-z]{1,2}_[^/]+/)+").unwrap());
+// Размер в пути аватара t.me/i/userpic/160/name.jpg
+static TELEGRAM_USERPIC_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(/i/userpic/)\d+(/)").unwrap());
+
+/// Ссылка на наибольший вариант картинки с CDN (`=s64`, `/S`, `?size=50x50`, `-150x150.png` ...).
+/// `None`, если в ссылке нечего увеличивать или она подписана
+pub fn upgrade_cdn_url(url: &str) -> Option<String> {
+    let mut parsed = Url::parse(url).ok()?;
+    let original = parsed.to_string();
+    let host = parsed.host_str()?.to_lowercase();
+
+    if parsed
+        .query_pairs()
+        .any(|(key, _)| SIGNATURE_PARAMS.contains(&key.to_lowercase().as_str()))
+    {
+        return None;
+    }
+
+    let upgraded = if host.ends_with("googleusercontent.com") {
+        google_image_size(url, GOOGLE_ORIGINAL_SIZE)
+    } else if host == "avatars.mds.yandex.net" {
+        yandex_avatar_size(url, YANDEX_MAX_SIZE)
+    } else if host.ends_with("userapi.com") || host.ends_with("vkuserphoto.ru") {
+        remove_query_params(&mut parsed, |key| VK_SIZE_PARAMS.contains(&key));
+        parsed.to_string()
+    } else if host == "t.me" || host == "telegram.me" {
+        let path = TELEGRAM_USERPIC_REGEX
+            .replace(
+                parsed.path(),
+                format!("${{1}}{TELEGRAM_USERPIC_SIZE}${{2}}").as_str(),
+            )
+            .into_owned();
+        parsed.set_path(&path);
+        parsed.to_string()
+    } else if host.ends_with("cloudinary.com") {
+        let path = CLOUDINARY_TRANSFORM_REGEX
+            .replace(parsed.path(), "${1}")
+            .into_owned();
+        parsed.set_path(&path);
+        parsed.to_string()
+    } else {
+        let path = THUMBNAIL_SUFFIX_REGEX
+            .replace(parsed.path(), "${1}")
+            .into_owned();
+        parsed.set_path(&path);
+        remove_query_params(&mut parsed, |key| SIZE_PARAMS.contains(&key));
+        parsed.to_string()
+    };
+
+    (upgraded != url && upgraded != original).then_some(upgraded)
+}
+
+/// Удаление параметров запроса, имя которых (в нижнем регистре) подходит под `remove`
+pub fn remove_query_params(url: &mut Url, remove: impl Fn(&str) -> bool) {
+    if url.query().is_none() {
+        return;
+    }
+    let params: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !remove(&key.to_lowercase()))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    if params.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(params);
+    }
+}
@@ -0,0 +1,269 @@
+use crate::config::ClaimMode;
+use crate::job_loaders::Jobs;
+use log::{error, info, warn};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const STATUS_IN_PROGRESS: &str = "IN_PROGRESS";
+const STATUS_OPEN: &str = "OPEN";
+// Захват в файле блокировок считается брошенным через сутки
+const CLAIM_TTL_SECS: i64 = 24 * 60 * 60;
+const LOCK_RETRIES: u32 = 50;
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(100);
+// Файл-мьютекс старше этого времени остался от упавшего процесса
+const STALE_LOCK: Duration = Duration::from_secs(30);
+// Ответы сервера, означающие что смена статуса заявки не поддерживается
+const SERVER_UNSUPPORTED: [StatusCode; 3] = [
+    StatusCode::NOT_FOUND,
+    StatusCode::METHOD_NOT_ALLOWED,
+    StatusCode::NOT_IMPLEMENTED,
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ClaimEntry {
+    owner: String,
+    claimed_at: i64,
+}
+
+/// Захват заявок на логотипы, чтобы несколько операторов не обрабатывали одну пачку.
+/// Заявки записываются в общий файл блокировок или, если это включено в конфигурации,
+/// переводятся в статус "в работе" на сервере
+pub struct LogoClaims {
+    http_client: Client,
+    base_url: String,
+    owner: String,
+    lock_file: PathBuf,
+    mode: ClaimMode,
+    claimed: Vec<u32>,
+}
+
+impl LogoClaims {
+    pub fn new(
+        http_client: Client,
+        base_url: &str,
+        owner: &str,
+        lock_file: &Path,
+        mode: ClaimMode,
+    ) -> Self {
+        Self {
+            http_client,
+            base_url: base_url.to_string(),
+            owner: owner.to_string(),
+            lock_file: lock_file.to_path_buf(),
+            mode,
+            claimed: Vec::new(),
+        }
+    }
+
+    /// Идентификаторы заявок, захваченных в этом запуске
+    pub fn claimed(&self) -> &[u32] {
+        &self.claimed
+    }
+
+    /// Захватывает заявки и возвращает задание только из успешно захваченных
+    pub async fn claim(&mut self, jobs: Jobs) -> Result<Jobs, Box<dyn Error + Send + Sync>> {
+        let total = jobs.logos.len();
+
+        self.claimed = match self.mode {
+            ClaimMode::Server => self.claim_on_server(&jobs).await?,
+            ClaimMode::LockFile => self.claim_in_lock_file(&jobs).await?,
+        };
+
+        let logos: Vec<_> = jobs
+            .logos
+            .into_iter()
+            .filter(|logo| self.claimed.contains(&logo.id))
+            .collect();
+
+        println!("Захвачено заявок {} из {}", logos.len(), total);
+        info!(
+            "Оператор {} захватил заявок {} из {} ({:?})",
+            self.owner,
+            logos.len(),
+            total,
+            self.mode
+        );
+        Ok(Jobs { logos })
+    }
+
+    /// Освобождает захваченные заявки. Ошибки только логируются, чтобы не скрыть
+    /// исходную причину сбоя запуска
+    pub async fn release(&mut self) {
+        if self.claimed.is_empty() {
+            return;
+        }
+        println!("Освобождение захваченных заявок: {}", self.claimed.len());
+
+        match self.mode {
+            ClaimMode::Server => {
+                for &id in &self.claimed {
+                    match self.set_server_status(id, STATUS_OPEN).await {
+                        Ok(status) if status.is_success() => {}
+                        Ok(status) => error!("Не удалось освободить заявку {id}: {status}"),
+                        Err(e) => error!("Не удалось освободить заявку {id}: {e}"),
+                    }
+                }
+            }
+            ClaimMode::LockFile => {
+                let released = self
+                    .with_lock_file(|entries| {
+                        entries.retain(|id, entry| {
+                            !(self.claimed.contains(id) && entry.owner == self.owner)
+                        })
+                    })
+                    .await;
+                if let Err(e) = released {
+                    error!(
+                        "Не удалось освободить заявки в файле {}: {e}",
+                        self.lock_file.display()
+                    );
+                }
+            }
+        }
+
+        info!("Освобождено заявок: {}", self.claimed.len());
+        self.claimed.clear();
+    }
+
+    /// Захват на сервере. Ошибка любой заявки, кроме занятой другим оператором,
+    /// останавливает захват и освобождает уже захваченные
+    async fn claim_on_server(
+        &mut self,
+        jobs: &Jobs,
+    ) -> Result<Vec<u32>, Box<dyn Error + Send + Sync>> {
+        let mut claimed = Vec::new();
+
+        for logo in &jobs.logos {
+            let error = match self.set_server_status(logo.id, STATUS_IN_PROGRESS).await {
+                Ok(status) if status.is_success() => {
+                    claimed.push(logo.id);
+                    continue;
+                }
+                Ok(StatusCode::CONFLICT) => {
+                    info!("Заявка {} уже в работе у другого оператора", logo.id);
+                    continue;
+                }
+                Ok(status @ (StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)) => format!(
+                    "Сервер отказал в изменении заявки {} ({status}): у пользователя нет разрешения на обновление заявок",
+                    logo.id
+                ),
+                Ok(status) if SERVER_UNSUPPORTED.contains(&status) => format!(
+                    "Сервер не поддерживает смену статуса заявки {} ({status}), \
+                     используйте --claim-mode lock-file",
+                    logo.id
+                ),
+                Ok(status) => format!("Ошибка захвата заявки {}: {}", logo.id, status),
+                Err(e) => format!("Ошибка захвата заявки {}: {}", logo.id, e),
+            };
+
+            // Не оставляем висеть то, что успели захватить
+            self.claimed = claimed;
+            self.release().await;
+            return Err(error.into());
+        }
+
+        Ok(claimed)
+    }
+
+    /// Смена статуса заявки. Адрес не из описания API сервера, поэтому режим включается явно
+    /// и неподдерживаемый ответ считается ошибкой, а не поводом перейти на файл блокировок
+    async fn set_server_status(&self, id: u32, status: &str) -> Result<StatusCode, reqwest::Error> {
+        let url = format!("{}/service/logoRequest/{id}/status", self.base_url);
+        let response = self
+            .http_client
+            .post(&url)
+            .json(&serde_json::json!({ "status": status }))
+            .send()
+            .await?;
+        Ok(response.status())
+    }
+
+    async fn claim_in_lock_file(
+        &self,
+        jobs: &Jobs,
+    ) -> Result<Vec<u32>, Box<dyn Error + Send + Sync>> {
+        let now = chrono::Local::now().timestamp();
+
+        self.with_lock_file(|entries| {
+            entries.retain(|_, entry| now - entry.claimed_at < CLAIM_TTL_SECS);
+
+            let mut claimed = Vec::new();
+            for logo in &jobs.logos {
+                match entries.get(&logo.id) {
+                    Some(entry) if entry.owner != self.owner => info!(
+                        "Заявка {} уже в работе у оператора {}",
+                        logo.id, entry.owner
+                    ),
+                    _ => {
+                        entries.insert(
+                            logo.id,
+                            ClaimEntry {
+                                owner: self.owner.clone(),
+                                claimed_at: now,
+                            },
+                        );
+                        claimed.push(logo.id);
+                    }
+                }
+            }
+            claimed
+        })
+        .await
+    }
+
+    /// Чтение и запись файла блокировок под файлом-мьютексом `<lock_file>.lock`
+    async fn with_lock_file<T>(
+        &self,
+        f: impl FnOnce(&mut HashMap<u32, ClaimEntry>) -> T,
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        let guard_path = self.lock_file.with_extension("lock");
+        Self::acquire_guard(&guard_path).await?;
+
+        let result = (|| -> Result<T, Box<dyn Error + Send + Sync>> {
+            let mut entries: HashMap<u32, ClaimEntry> = if self.lock_file.exists() {
+                serde_json::from_str(&fs::read_to_string(&self.lock_file)?)?
+            } else {
+                HashMap::new()
+            };
+            let value = f(&mut entries);
+            fs::write(&self.lock_file, serde_json::to_string_pretty(&entries)?)?;
+            Ok(value)
+        })();
+
+        let _ = fs::remove_file(&guard_path);
+        result
+    }
+
+    /// Ожидание файла-мьютекса без блокировки потока tokio
+    async fn acquire_guard(guard_path: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for _ in 0..LOCK_RETRIES {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(guard_path)
+            {
+                Ok(_) => return Ok(()),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let is_stale = fs::metadata(guard_path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > STALE_LOCK);
+                    if is_stale {
+                        warn!("Удаление брошенной блокировки {}", guard_path.display());
+                        let _ = fs::remove_file(guard_path);
+                    } else {
+                        tokio::time::sleep(LOCK_RETRY_DELAY).await;
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(format!("Файл блокировок занят: {}", guard_path.display()).into())
+    }
+}
@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -12,8 +12,38 @@ pub const RESULT_FOLDER: &str = "Logo/Result";
 pub const CROP_FOLDER: &str = "Logo/Crop";
 pub const TEMP_JOB_FILE: &str = "job.json";
 pub const SVG_REWORK_FOLDER: &str = "Logo/Rework";
+// Кэш не входит в all_folders и не очищается между запусками
+pub const CACHE_FOLDER: &str = "Logo/Cache";
+// Встроенные в страницы SVG логотипы, сохранённые при разборе ссылок
+pub const INLINE_SVG_FOLDER: &str = "Logo/Inline";
+pub const CLAIM_FILE: &str = "logo_claims.json";
+pub const RULES_FILE: &str = "logo_rules.toml";
+pub const DOWNLOAD_REPORT_FILE: &str = "download_report.json";
 pub const DOWNLOAD: bool = true;
 pub const UPSCALE: bool = true;
+pub const CLAIM: bool = false;
+pub const PROBE_CANDIDATES: usize = 0;
+pub const EXPAND_LINKS: bool = true;
+pub const CONNECT_TIMEOUT_SECS: u64 = 10;
+pub const READ_TIMEOUT_SECS: u64 = 30;
+pub const DOWNLOAD_RETRIES: u32 = 3;
+pub const OFFLINE: bool = false;
+pub const SVG_MODE: SvgMode = SvgMode::Rework;
+pub const CLAIM_MODE: ClaimMode = ClaimMode::LockFile;
+// Разрешения сервера на список и изменение заявок на логотипы. Их идентификаторы
+// не опубликованы: значения берутся из поля permissions ответа /service/user/info
+// (пишется в лог при входе). Пока список пуст, права проверяет только сервер,
+// а при входе выводится предупреждение
+pub const LOGO_PERMISSIONS: [&str; 0] = [];
+// Известные пути иконок, проверяемые если страница сайта не объявляет логотип
+pub const WELL_KNOWN_PATHS: [&str; 6] = [
+    "/apple-touch-icon.png",
+    "/apple-touch-icon-precomposed.png",
+    "/favicon.svg",
+    "/logo.svg",
+    "/logo.png",
+    "/favicon.ico",
+];
 
 // Пути по умолчанию для Upscayl (macOS)
 pub const DEFAULT_UPSCALER_PROG: &str =
@@ -21,6 +51,28 @@ pub const DEFAULT_UPSCALER_PROG: &str =
 pub const DEFAULT_MODEL_PATH: &str = "/Applications/Upscayl.app/Contents/Resources/models";
 pub const DEFAULT_MODEL_NAME: &str = "upscayl-standard-4x";
 
+/// Что делать со скачанными SVG логотипами
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SvgMode {
+    /// Сохранить как есть в Logo/Rework для ручной доработки
+    Rework,
+    /// Отрисовать в Logo/Raw и обработать вместе с растровыми логотипами
+    Rasterize,
+    /// Вписать исходные пути без фона в итоговый SVG 300x300 в Logo/Result
+    Vector,
+}
+
+/// Где отмечаются захваченные заявки на логотипы
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClaimMode {
+    /// Общий файл блокировок операторов
+    LockFile,
+    /// Статус заявки на сервере. API смены статуса не описано, включается явно
+    Server,
+}
+
 /// Структура для конфигурационного файла
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ConfigFile {
@@ -28,6 +80,20 @@ pub struct ConfigFile {
     pub out_dir: Option<String>,
     pub download: Option<bool>,
     pub upscale: Option<bool>,
+    pub claim: Option<bool>,
+    pub claim_file: Option<String>,
+    pub claim_mode: Option<ClaimMode>,
+    pub probe_candidates: Option<usize>,
+    pub well_known_paths: Option<Vec<String>>,
+    pub rules_file: Option<String>,
+    pub expand_links: Option<bool>,
+    pub connect_timeout: Option<u64>,
+    pub read_timeout: Option<u64>,
+    pub retries: Option<u32>,
+    pub cache_dir: Option<String>,
+    pub offline: Option<bool>,
+    pub svg_mode: Option<SvgMode>,
+    pub logo_permissions: Option<Vec<String>>,
     pub upscayl: Option<UpscaylConfig>,
 }
 
@@ -60,6 +126,62 @@ pub struct Config {
     #[arg(long)]
     pub upscale: Option<bool>,
 
+    /// Claim logo requests so other operators skip them
+    #[arg(long)]
+    pub claim: Option<bool>,
+
+    /// Shared lock file for claims in lock-file mode
+    #[arg(long)]
+    pub claim_file: Option<String>,
+
+    /// Where claims are recorded: shared lock file or request status on the server
+    #[arg(long, value_enum)]
+    pub claim_mode: Option<ClaimMode>,
+
+    /// Download top N website logo candidates and rank them by real size (0 disables)
+    #[arg(long)]
+    pub probe_candidates: Option<usize>,
+
+    /// Well-known icon paths probed when a website declares no logo (comma separated, empty disables)
+    #[arg(long, value_delimiter = ',')]
+    pub well_known_paths: Option<Vec<String>>,
+
+    /// TOML file with per-site logo extraction rules
+    #[arg(long)]
+    pub rules_file: Option<String>,
+
+    /// Expand short links (clck.ru, bit.ly ...) before detecting the site type
+    #[arg(long)]
+    pub expand_links: Option<bool>,
+
+    /// Connect timeout in seconds for page and image requests
+    #[arg(long)]
+    pub connect_timeout: Option<u64>,
+
+    /// Read timeout in seconds for page and image requests
+    #[arg(long)]
+    pub read_timeout: Option<u64>,
+
+    /// Retries of image downloads on network errors and 5xx/429 responses
+    #[arg(long)]
+    pub retries: Option<u32>,
+
+    /// Directory of the downloaded images cache
+    #[arg(long)]
+    pub cache_dir: Option<String>,
+
+    /// Take images only from the cache without network requests
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub offline: Option<bool>,
+
+    /// How to handle downloaded SVG logos
+    #[arg(long, value_enum)]
+    pub svg_mode: Option<SvgMode>,
+
+    /// Server permissions required to list and update logo requests (comma separated)
+    #[arg(long, value_delimiter = ',')]
+    pub logo_permissions: Option<Vec<String>>,
+
     /// Path to upscayl binary
     #[arg(long)]
     pub upscayl_bin: Option<String>,
@@ -160,6 +282,46 @@ impl Config {
             upscale: self
                 .upscale
                 .or(file_config.as_ref().and_then(|f| f.upscale)),
+            claim: self.claim.or(file_config.as_ref().and_then(|f| f.claim)),
+            claim_file: self
+                .claim_file
+                .or(file_config.as_ref().and_then(|f| f.claim_file.clone())),
+            claim_mode: self
+                .claim_mode
+                .or(file_config.as_ref().and_then(|f| f.claim_mode)),
+            probe_candidates: self
+                .probe_candidates
+                .or(file_config.as_ref().and_then(|f| f.probe_candidates)),
+            well_known_paths: self.well_known_paths.or(file_config
+                .as_ref()
+                .and_then(|f| f.well_known_paths.clone())),
+            rules_file: self
+                .rules_file
+                .or(file_config.as_ref().and_then(|f| f.rules_file.clone())),
+            expand_links: self
+                .expand_links
+                .or(file_config.as_ref().and_then(|f| f.expand_links)),
+            connect_timeout: self
+                .connect_timeout
+                .or(file_config.as_ref().and_then(|f| f.connect_timeout)),
+            read_timeout: self
+                .read_timeout
+                .or(file_config.as_ref().and_then(|f| f.read_timeout)),
+            retries: self
+                .retries
+                .or(file_config.as_ref().and_then(|f| f.retries)),
+            cache_dir: self
+                .cache_dir
+                .or(file_config.as_ref().and_then(|f| f.cache_dir.clone())),
+            offline: self
+                .offline
+                .or(file_config.as_ref().and_then(|f| f.offline)),
+            svg_mode: self
+                .svg_mode
+                .or(file_config.as_ref().and_then(|f| f.svg_mode)),
+            logo_permissions: self.logo_permissions.or(file_config
+                .as_ref()
+                .and_then(|f| f.logo_permissions.clone())),
             upscayl_bin: self.upscayl_bin.or(upscayl.and_then(|u| u.bin.clone())),
             upscayl_models: self
                 .upscayl_models
@@ -188,6 +350,86 @@ impl Config {
         self.upscale.unwrap_or(UPSCALE)
     }
 
+    /// Получить значение claim (гарантированно Some после load_from_file)
+    pub fn claim(&self) -> bool {
+        self.claim.unwrap_or(CLAIM)
+    }
+
+    /// Получить путь к общему файлу блокировок заявок
+    pub fn claim_file(&self) -> PathBuf {
+        match &self.claim_file {
+            Some(path) => PathBuf::from(path),
+            None => Path::new(self.out_dir()).join(CLAIM_FILE),
+        }
+    }
+
+    /// Получить способ захвата заявок
+    pub fn claim_mode(&self) -> ClaimMode {
+        self.claim_mode.unwrap_or(CLAIM_MODE)
+    }
+
+    /// Получить количество кандидатов в логотипы сайта, проверяемых скачиванием
+    pub fn probe_candidates(&self) -> usize {
+        self.probe_candidates.unwrap_or(PROBE_CANDIDATES)
+    }
+
+    /// Получить известные пути иконок сайта для проверки
+    pub fn well_known_paths(&self) -> Vec<String> {
+        match &self.well_known_paths {
+            Some(paths) => paths.clone(),
+            None => WELL_KNOWN_PATHS
+                .iter()
+                .map(|path| path.to_string())
+                .collect(),
+        }
+    }
+
+    /// Получить путь к файлу правил извлечения логотипов для сайтов
+    pub fn rules_file(&self) -> PathBuf {
+        PathBuf::from(self.rules_file.as_deref().unwrap_or(RULES_FILE))
+    }
+
+    /// Получить значение expand_links
+    pub fn expand_links(&self) -> bool {
+        self.expand_links.unwrap_or(EXPAND_LINKS)
+    }
+
+    /// Получить таймаут соединения при загрузке страниц и картинок в секундах
+    pub fn connect_timeout(&self) -> u64 {
+        self.connect_timeout.unwrap_or(CONNECT_TIMEOUT_SECS)
+    }
+
+    /// Получить таймаут чтения при загрузке страниц и картинок в секундах
+    pub fn read_timeout(&self) -> u64 {
+        self.read_timeout.unwrap_or(READ_TIMEOUT_SECS)
+    }
+
+    /// Получить количество повторов скачивания картинки
+    pub fn retries(&self) -> u32 {
+        self.retries.unwrap_or(DOWNLOAD_RETRIES)
+    }
+
+    /// Получить значение offline
+    pub fn offline(&self) -> bool {
+        self.offline.unwrap_or(OFFLINE)
+    }
+
+    /// Получить режим обработки SVG логотипов
+    pub fn svg_mode(&self) -> SvgMode {
+        self.svg_mode.unwrap_or(SVG_MODE)
+    }
+
+    /// Получить разрешения сервера, необходимые для работы с заявками на логотипы
+    pub fn logo_permissions(&self) -> Vec<String> {
+        match &self.logo_permissions {
+            Some(permissions) => permissions.clone(),
+            None => LOGO_PERMISSIONS
+                .iter()
+                .map(|permission| permission.to_string())
+                .collect(),
+        }
+    }
+
     /// Получить значение upscayl_bin (гарантированно Some после load_from_file)
     pub fn upscayl_bin(&self) -> &str {
         self.upscayl_bin.as_deref().unwrap_or(DEFAULT_UPSCALER_PROG)
@@ -227,11 +469,30 @@ impl Config {
         Path::new(self.out_dir()).join(CROP_FOLDER)
     }
 
+    /// Получить путь к директории кэша скачанных картинок
+    pub fn cache_folder(&self) -> PathBuf {
+        match &self.cache_dir {
+            Some(path) => PathBuf::from(path),
+            None => Path::new(self.out_dir()).join(CACHE_FOLDER),
+        }
+    }
+
+    /// Получить путь к директории встроенных SVG. Её нет в `all_folders`:
+    /// файлы пишутся при разборе ссылок, до очистки папок перед скачиванием
+    pub fn inline_svg_folder(&self) -> PathBuf {
+        Path::new(self.out_dir()).join(INLINE_SVG_FOLDER)
+    }
+
     /// Получить полный путь к файлу лога
     pub fn log_file(&self) -> PathBuf {
         Path::new(self.out_dir()).join(LOG_FILE)
     }
 
+    /// Получить полный путь к отчёту о скачивании логотипов
+    pub fn download_report_file(&self) -> PathBuf {
+        Path::new(self.out_dir()).join(DOWNLOAD_REPORT_FILE)
+    }
+
     /// Получить полный путь к временному файлу заданий
     pub fn temp_job_file(&self) -> PathBuf {
         Path::new(self.out_dir()).join(TEMP_JOB_FILE)
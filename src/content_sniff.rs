@@ -0,0 +1,205 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use image::ImageFormat;
+use std::fmt;
+
+// Сколько байт начала файла просматривается для текстовых форматов
+const SNIFF_BYTES: usize = 1024;
+// Сколько байт пролога XML (объявление, комментарии, DOCTYPE) пропускается до корня SVG
+const PROLOG_SCAN_BYTES: usize = 64 * 1024;
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const UTF8_BOM: &[u8] = &[0xef, 0xbb, 0xbf];
+// Сколько символов тела ответа показывать в ошибке
+const ERROR_SNIPPET_CHARS: usize = 120;
+
+/// Тип содержимого по первым байтам файла, без доверия ссылке и Content-Type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContentKind {
+    Raster(ImageFormat),
+    /// Картинка в формате, который не читается при включённых фичах крейта image (AVIF, TIFF ...)
+    UnsupportedImage(ImageFormat),
+    Svg,
+    /// Страница входа, капчи или ошибки вместо картинки
+    Html,
+    /// Ответ API с ошибкой
+    Json,
+    /// Сжатые данные (gzip), например .svgz
+    Gzip,
+    Unknown,
+}
+
+/// Почему скачанный файл не удалось принять как логотип
+#[derive(Debug)]
+pub enum ContentError {
+    Html,
+    Json(String),
+    Gzip,
+    Unknown(Option<String>),
+    Unsupported(ImageFormat),
+    Decode(String),
+    DataUri(String),
+}
+
+impl fmt::Display for ContentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentError::Html => {
+                write!(f, "вместо картинки HTML страница (вход, капча или ошибка)")
+            }
+            ContentError::Json(snippet) => write!(f, "вместо картинки ответ JSON: {snippet}"),
+            ContentError::Gzip => write!(f, "сжатый файл (gzip) вместо картинки"),
+            ContentError::Unknown(content_type) => write!(
+                f,
+                "неизвестный формат файла (Content-Type: {})",
+                content_type.as_deref().unwrap_or("нет")
+            ),
+            ContentError::Unsupported(format) => write!(
+                f,
+                "формат картинки {} не поддерживается",
+                format.to_mime_type()
+            ),
+            ContentError::Decode(e) => write!(f, "картинка повреждена: {e}"),
+            ContentError::DataUri(e) => write!(f, "некорректная data: ссылка: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ContentError {}
+
+/// Определение типа содержимого по сигнатуре
+pub fn sniff_content(bytes: &[u8]) -> ContentKind {
+    if bytes.starts_with(GZIP_MAGIC) {
+        return ContentKind::Gzip;
+    }
+    if let Ok(format) = image::guess_format(bytes) {
+        return if format.reading_enabled() {
+            ContentKind::Raster(format)
+        } else {
+            ContentKind::UnsupportedImage(format)
+        };
+    }
+
+    let text = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+    let text = String::from_utf8_lossy(&text[..text.len().min(PROLOG_SCAN_BYTES)]);
+
+    // Корень документа после пролога: у SVG перед ним бывают длинные комментарии и DOCTYPE
+    if skip_xml_prolog(&text)
+        .get(..4)
+        .is_some_and(|tag| tag.eq_ignore_ascii_case("<svg"))
+    {
+        return ContentKind::Svg;
+    }
+
+    let head: String = text
+        .chars()
+        .take(SNIFF_BYTES)
+        .collect::<String>()
+        .to_lowercase();
+    let head = head.trim_start();
+
+    if head.starts_with("<!doctype html") || head.contains("<html") || head.contains("<body") {
+        ContentKind::Html
+    } else if head.starts_with('{') || head.starts_with('[') {
+        ContentKind::Json
+    } else {
+        ContentKind::Unknown
+    }
+}
+
+/// Пропуск объявления XML, инструкций обработки, комментариев и DOCTYPE до первого элемента
+fn skip_xml_prolog(mut text: &str) -> &str {
+    loop {
+        let rest = text.trim_start();
+        let end = if rest.starts_with("<?") {
+            rest.find("?>").map(|end| end + 2)
+        } else if rest.starts_with("<!--") {
+            rest.find("-->").map(|end| end + 3)
+        } else if rest
+            .get(..9)
+            .is_some_and(|tag| tag.eq_ignore_ascii_case("<!doctype"))
+        {
+            doctype_end(rest)
+        } else {
+            return rest;
+        };
+        match end {
+            Some(end) => text = &rest[end..],
+            // Пролог не закончился в просмотренной части
+            None => return "",
+        }
+    }
+}
+
+/// Конец DOCTYPE с учётом внутреннего подмножества `[...]`, где встречается `>`
+fn doctype_end(doctype: &str) -> Option<usize> {
+    let close = doctype.find('>')?;
+    match doctype[..close].find('[') {
+        Some(open) => {
+            let subset_end = open + doctype[open..].find(']')?;
+            Some(subset_end + doctype[subset_end..].find('>')? + 1)
+        }
+        None => Some(close + 1),
+    }
+}
+
+/// Ошибка для содержимого, которое не является картинкой
+pub fn content_error(
+    kind: ContentKind,
+    bytes: &[u8],
+    content_type: Option<String>,
+) -> ContentError {
+    match kind {
+        ContentKind::Html => ContentError::Html,
+        ContentKind::Json => ContentError::Json(
+            String::from_utf8_lossy(bytes)
+                .chars()
+                .take(ERROR_SNIPPET_CHARS)
+                .collect(),
+        ),
+        ContentKind::Gzip => ContentError::Gzip,
+        ContentKind::UnsupportedImage(format) => ContentError::Unsupported(format),
+        _ => ContentError::Unknown(content_type),
+    }
+}
+
+/// Разбор `data:[<тип>][;base64],<данные>` в тип и байты
+pub fn decode_data_uri(url: &str) -> Result<(Option<String>, Vec<u8>), ContentError> {
+    let data = url
+        .strip_prefix("data:")
+        .ok_or_else(|| ContentError::DataUri("нет префикса data:".to_string()))?;
+    let (header, payload) = data
+        .split_once(',')
+        .ok_or_else(|| ContentError::DataUri("нет данных после запятой".to_string()))?;
+
+    let is_base64 = header.to_lowercase().ends_with(";base64");
+    let media_type = header
+        .split(';')
+        .next()
+        .filter(|media_type| !media_type.is_empty())
+        .map(String::from);
+
+    let bytes = if is_base64 {
+        let payload: String = urlencoding::decode(payload)
+            .map_err(|e| ContentError::DataUri(e.to_string()))?
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        BASE64
+            .decode(payload)
+            .map_err(|e| ContentError::DataUri(e.to_string()))?
+    } else {
+        urlencoding::decode_binary(payload.as_bytes()).into_owned()
+    };
+    Ok((media_type, bytes))
+}
+
+/// Ссылка для логов: у data: ссылки только тип и размер, без содержимого
+pub fn display_url(url: &str) -> String {
+    match url.strip_prefix("data:") {
+        Some(data) => {
+            let header = data.split(',').next().unwrap_or_default();
+            format!("data:{header},... ({} байт)", url.len())
+        }
+        None => url.to_string(),
+    }
+}
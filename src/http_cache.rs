@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Кэш скачанных картинок на диске: тело ответа и метаданные для условных запросов.
+/// Ключ — ссылка, по ней стабильный хэш имени файла
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    dir: PathBuf,
+}
+
+/// Сохранённый ответ сервера
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_type: Option<String>,
+    #[serde(skip)]
+    pub body: Vec<u8>,
+}
+
+impl HttpCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Ответ из кэша. `None`, если ссылки в кэше нет или файлы повреждены
+    pub async fn load(&self, url: &str) -> Option<CachedResponse> {
+        let (meta_path, body_path) = self.paths(url);
+        let meta = tokio::fs::read_to_string(&meta_path).await.ok()?;
+        let mut cached: CachedResponse = serde_json::from_str(&meta).ok()?;
+        // Совпадение хэшей разных ссылок
+        if cached.url != url {
+            return None;
+        }
+        cached.body = tokio::fs::read(&body_path).await.ok()?;
+        Some(cached)
+    }
+
+    pub async fn store(
+        &self,
+        cached: &CachedResponse,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let (meta_path, body_path) = self.paths(&cached.url);
+        tokio::fs::create_dir_all(&self.dir).await?;
+        // Метаданные пишутся последними: без них запись не считается сохранённой
+        tokio::fs::write(&body_path, &cached.body).await?;
+        tokio::fs::write(&meta_path, serde_json::to_string_pretty(cached)?).await?;
+        Ok(())
+    }
+
+    fn paths(&self, url: &str) -> (PathBuf, PathBuf) {
+        let key = cache_key(url);
+        (
+            self.dir.join(format!("{key}.json")),
+            self.dir.join(format!("{key}.bin")),
+        )
+    }
+}
+
+/// FNV-1a: хэш не зависит от версии компилятора, поэтому кэш переживает пересборку
+fn cache_key(url: &str) -> String {
+    let hash = url.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    });
+    format!("{hash:016x}")
+}
@@ -1,98 +1,556 @@
-use crate::config::Config;
+use crate::background_works::DominantColor;
+use crate::cdn_urls::upgrade_cdn_url;
+use crate::config::{Config, SvgMode};
+use crate::content_sniff::{
+    content_error, decode_data_uri, display_url, sniff_content, ContentError, ContentKind,
+};
+use crate::http_cache::{CachedResponse, HttpCache};
+use crate::image_worker::{result_background, WHITE_BG_REPLACE_GRAY};
 use crate::job_loaders::{Jobs, LogoJob};
+use crate::svg_render::{
+    flatten_on_white, has_transparent_background, parse_svg, rasterize_svg, strip_background,
+};
+use crate::svg_sanitizer::sanitize_svg_bytes;
+use crate::svg_saver::save_source_vector_logo;
 use futures::stream::{self, StreamExt};
-use image::ImageFormat;
-use log::{error, info};
+use image::{DynamicImage, ImageFormat};
+use log::{error, info, warn};
+use reqwest::header::{
+    CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER,
+};
+use reqwest::StatusCode;
+use serde::Serialize;
 use std::error::Error;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 
 const DOWNLOAD_CONCURRENCY: usize = 16;
+// Начальная пауза перед повтором, дальше удваивается
+pub const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+pub const RETRY_MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// Попытка скачивания логотипа для отчёта о запуске
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadAttempt {
+    pub url: String,
+    pub attempt: u32,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+    /// Картинка взята из кэша (ответ 304 или режим offline)
+    pub cached: bool,
+}
+
+/// Итог скачивания логотипа со всеми попытками
+#[derive(Debug, Clone, Serialize)]
+pub struct LogoDownloadReport {
+    pub id: u32,
+    pub file: Option<PathBuf>,
+    pub error: Option<String>,
+    pub attempts: Vec<DownloadAttempt>,
+}
+
+/// Настройки повторов скачивания
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub retries: u32,
+}
+
+impl RetryPolicy {
+    /// Экспоненциальная пауза со случайным разбросом, чтобы параллельные загрузки
+    /// не повторяли запросы к серверу одновременно
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = RETRY_BASE_DELAY
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(RETRY_MAX_DELAY);
+        let half = backoff.as_millis() as u64 / 2;
+        Duration::from_millis(half + fastrand::u64(0..=half))
+    }
+}
+
+/// Клиент скачивания с настройками повторов и кэшем
+struct Downloader {
+    client: reqwest::Client,
+    retry: RetryPolicy,
+    cache: HttpCache,
+    /// Только из кэша, без обращения к сети
+    offline: bool,
+    svg_mode: SvgMode,
+}
+
+/// Куда сохраняются скачанные логотипы
+struct LogoFolders {
+    download: PathBuf,
+    inline_svg: PathBuf,
+    rework: PathBuf,
+    result: PathBuf,
+}
 
 // Скачать все изображения с сервера
 pub async fn download_images(
     job: &Jobs,
     config: &Config,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let client = reqwest::Client::new();
-    let download_folder = config.download_folder();
-    let rework_folder = config.rework_svg_folder();
-
-    let results: Vec<Result<(), Box<dyn Error + Send + Sync>>> =
-        stream::iter(job.logos.iter().cloned().enumerate())
-            .map(|(idx, logo)| {
-                let client = client.clone();
-                let download_folder = download_folder.clone();
-                let rework_folder = rework_folder.clone();
-                async move {
-                    download_single_logo(&client, idx, &logo, &download_folder, &rework_folder)
-                        .await
-                }
-            })
-            .buffer_unordered(DOWNLOAD_CONCURRENCY)
-            .collect()
-            .await;
+    let downloader = Downloader {
+        client: reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(config.connect_timeout()))
+            .read_timeout(Duration::from_secs(config.read_timeout()))
+            .build()?,
+        retry: RetryPolicy {
+            retries: config.retries(),
+        },
+        cache: HttpCache::new(config.cache_folder()),
+        offline: config.offline(),
+        svg_mode: config.svg_mode(),
+    };
+    if downloader.offline {
+        println!(
+            "Режим offline: картинки берутся только из кэша {}",
+            config.cache_folder().display()
+        );
+    }
+    let folders = LogoFolders {
+        download: config.download_folder(),
+        inline_svg: config.inline_svg_folder(),
+        rework: config.rework_svg_folder(),
+        result: config.result_folder(),
+    };
+
+    let mut reports: Vec<LogoDownloadReport> = stream::iter(job.logos.iter().cloned().enumerate())
+        .map(|(idx, logo)| {
+            let downloader = &downloader;
+            let folders = &folders;
+            async move { download_single_logo(downloader, idx, &logo, folders).await }
+        })
+        .buffer_unordered(DOWNLOAD_CONCURRENCY)
+        .collect()
+        .await;
+    reports.sort_by_key(|report| report.id);
+
+    let report_file = config.download_report_file();
+    match serde_json::to_string_pretty(&reports) {
+        Ok(json) => tokio::fs::write(&report_file, json).await?,
+        Err(e) => error!("Не удалось сохранить отчёт {}: {e}", report_file.display()),
+    }
+
+    let failed: Vec<&LogoDownloadReport> = reports.iter().filter(|r| r.file.is_none()).collect();
+    println!(
+        "Скачано логотипов {} из {}, отчёт: {}",
+        reports.len() - failed.len(),
+        reports.len(),
+        report_file.display()
+    );
 
     // Прерываемся на первой ошибке (можно поменять на накопление/summary при желании)
-    for result in results {
-        result?;
+    match failed.first() {
+        Some(report) => Err(report
+            .error
+            .clone()
+            .unwrap_or_else(|| format!("Логотип {} не скачан", report.id))
+            .into()),
+        None => Ok(()),
     }
-    Ok(())
 }
 
+/// Скачивание логотипа. Если основная ссылка не скачалась или не читается как картинка,
+/// пробуются запасные ссылки задания
 async fn download_single_logo(
-    client: &reqwest::Client,
+    downloader: &Downloader,
     idx: usize,
     logo: &LogoJob,
-    download_folder: &Path,
-    rework_folder: &Path,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let response = client.get(&logo.url).send().await?;
+    folders: &LogoFolders,
+) -> LogoDownloadReport {
+    // Сначала наибольший вариант картинки с CDN, при неудаче — исходная ссылка
+    let urls: Vec<String> = std::iter::once(&logo.url)
+        .chain(&logo.fallback_urls)
+        .flat_map(|url| upgrade_cdn_url(url).into_iter().chain([url.clone()]))
+        .collect();
+    let mut report = LogoDownloadReport {
+        id: logo.id,
+        file: None,
+        error: None,
+        attempts: Vec::new(),
+    };
+    let mut last_error = None;
+
+    // Встроенный SVG страницы уже сохранён при разборе ссылок, его ссылка — сама страница
+    if let Some(path) = &logo.inline_svg {
+        match save_inline_svg(downloader, path, logo.id, folders).await {
+            Ok(out_path) => {
+                info!(
+                    "{idx} Встроенный SVG '{}' -> {} сохранён",
+                    path.display(),
+                    out_path.display()
+                );
+                report.file = Some(out_path);
+            }
+            Err(e) => {
+                let error = format!(
+                    "Логотип {}: встроенный SVG {}: {e}",
+                    logo.id,
+                    path.display()
+                );
+                error!("{error}");
+                report.error = Some(error);
+            }
+        }
+        return report;
+    }
+
+    for url in &urls {
+        let result = save_logo(downloader, url, logo.id, folders, &mut report.attempts).await;
+        match result {
+            Ok(out_path) => {
+                info!(
+                    "{idx} Файл '{}' -> {} успешно скачан",
+                    display_url(url),
+                    out_path.display()
+                );
+                report.file = Some(out_path);
+                return report;
+            }
+            Err(e) => {
+                if urls.len() > 1 {
+                    warn!(
+                        "{idx} Логотип {}: ссылка '{}' не подошла: {e}",
+                        logo.id,
+                        display_url(url)
+                    );
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+
+    let error = last_error
+        .map(|e| format!("Логотип {}: {e}", logo.id))
+        .unwrap_or_else(|| "Нет ссылки на логотип".to_string());
+    error!("{error}");
+    report.error = Some(error);
+    report
+}
+
+async fn save_logo(
+    downloader: &Downloader,
+    url: &str,
+    id: u32,
+    folders: &LogoFolders,
+    attempts: &mut Vec<DownloadAttempt>,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let (content_type, bytes) = if url.starts_with("data:") {
+        // Картинка прямо в ссылке задания
+        decode_data_uri(url)?
+    } else if downloader.offline {
+        load_offline(downloader, url, attempts).await?
+    } else {
+        fetch_with_retries(downloader, url, attempts).await?
+    };
+
+    let result = store_logo(id, content_type, &bytes, downloader.svg_mode, folders).await;
+    // Ошибка разбора скачанного файла тоже попадает в отчёт
+    if let (Err(e), Some(attempt)) = (&result, attempts.last_mut()) {
+        if attempt.url == url && attempt.error.is_none() {
+            attempt.error = Some(e.to_string());
+        }
+    }
+    result
+}
+
+/// Встроенный SVG из папки, куда его записал разбор ссылок. Файлы вне этой папки не читаются
+async fn save_inline_svg(
+    downloader: &Downloader,
+    path: &Path,
+    id: u32,
+    folders: &LogoFolders,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let folder = tokio::fs::canonicalize(&folders.inline_svg).await?;
+    let path = tokio::fs::canonicalize(path).await?;
+    if !path.starts_with(&folder) {
+        return Err(format!("файл вне папки {}", folder.display()).into());
+    }
+
+    let bytes = tokio::fs::read(&path).await?;
+    store_logo(
+        id,
+        Some("image/svg+xml".to_string()),
+        &bytes,
+        downloader.svg_mode,
+        folders,
+    )
+    .await
+}
+
+/// Картинка из кэша в режиме offline
+async fn load_offline(
+    downloader: &Downloader,
+    url: &str,
+    attempts: &mut Vec<DownloadAttempt>,
+) -> Result<(Option<String>, Vec<u8>), Box<dyn Error + Send + Sync>> {
+    let cached = downloader.cache.load(url).await;
+    let error = cached
+        .is_none()
+        .then(|| format!("Нет в кэше (offline): '{url}'"));
+    attempts.push(DownloadAttempt {
+        url: url.to_string(),
+        attempt: 1,
+        status: None,
+        error: error.clone(),
+        elapsed_ms: 0,
+        cached: cached.is_some(),
+    });
+
+    match cached {
+        Some(cached) => Ok((cached.content_type, cached.body)),
+        None => Err(error.unwrap_or_default().into()),
+    }
+}
+
+/// Скачивание с повторами при сетевых ошибках и ответах 5xx/429
+async fn fetch_with_retries(
+    downloader: &Downloader,
+    url: &str,
+    attempts: &mut Vec<DownloadAttempt>,
+) -> Result<(Option<String>, Vec<u8>), Box<dyn Error + Send + Sync>> {
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+        let started = Instant::now();
+        let result = fetch(downloader, url).await;
 
-    if response.status().is_success() {
-        let is_svg = response
+        let (status, error, retry_after) = match &result {
+            Ok(fetched) => (Some(fetched.status), None, None),
+            Err(FetchError::Status(status, retry_after)) => {
+                (Some(*status), Some(status.to_string()), *retry_after)
+            }
+            Err(FetchError::Network(e)) => (None, Some(e.to_string()), None),
+        };
+        attempts.push(DownloadAttempt {
+            url: url.to_string(),
+            attempt,
+            status: status.map(|s| s.as_u16()),
+            error,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            cached: result
+                .as_ref()
+                .is_ok_and(|fetched| fetched.status == StatusCode::NOT_MODIFIED),
+        });
+
+        let error = match result {
+            Ok(fetched) => return Ok((fetched.content_type, fetched.body)),
+            Err(e) => e,
+        };
+        if !error.is_transient() || attempt > downloader.retry.retries {
+            return Err(error.into_message(url).into());
+        }
+
+        // Retry-After сервера важнее своей паузы, но не дольше предела
+        let delay = retry_after
+            .map(|after| after.min(RETRY_MAX_DELAY))
+            .unwrap_or_else(|| downloader.retry.delay(attempt - 1));
+        warn!(
+            "Повтор скачивания '{url}' через {} мс (попытка {attempt}): {}",
+            delay.as_millis(),
+            attempts
+                .last()
+                .and_then(|a| a.error.as_deref())
+                .unwrap_or_default()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+enum FetchError {
+    /// Неуспешный ответ сервера и его Retry-After
+    Status(StatusCode, Option<Duration>),
+    Network(reqwest::Error),
+}
+
+impl FetchError {
+    fn is_transient(&self) -> bool {
+        match self {
+            FetchError::Status(status, _) => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            FetchError::Network(e) => !e.is_builder() && !e.is_redirect(),
+        }
+    }
+
+    fn into_message(self, url: &str) -> String {
+        match self {
+            FetchError::Status(status, _) => {
+                format!("Ошибка загрузки '{}'. Код статуса: {}", url, status)
+            }
+            FetchError::Network(e) => format!("Ошибка загрузки '{}': {}", url, e.without_url()),
+        }
+    }
+}
+
+/// Ответ сервера на скачивание картинки
+struct Fetched {
+    status: StatusCode,
+    content_type: Option<String>,
+    body: Vec<u8>,
+}
+
+/// Условный запрос по ETag/Last-Modified из кэша. На 304 отдаётся тело из кэша,
+/// новый ответ сохраняется в кэш
+async fn fetch(downloader: &Downloader, url: &str) -> Result<Fetched, FetchError> {
+    let cached = downloader.cache.load(url).await;
+
+    let mut request = downloader.client.get(url);
+    if let Some(cached) = &cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request.send().await.map_err(FetchError::Network)?;
+    let status = response.status();
+
+    if status == StatusCode::NOT_MODIFIED {
+        if let Some(cached) = cached {
+            return Ok(Fetched {
+                status,
+                content_type: cached.content_type,
+                body: cached.body,
+            });
+        }
+    }
+    if !status.is_success() {
+        let retry_after = response
             .headers()
-            .get(reqwest::header::CONTENT_TYPE)
+            .get(RETRY_AFTER)
             .and_then(|v| v.to_str().ok())
-            .is_some_and(|ct| ct.starts_with("image/svg+xml"))
-            || logo.url.to_lowercase().contains(".svg");
-
-        // Контракт хранения:
-        // - растровые: download_folder/<id> (без расширения)
-        // - svg: rework_folder/<id>.svg
-        let out_path: PathBuf = if is_svg {
-            rework_folder.join(format!("{}.svg", logo.id))
-        } else {
-            download_folder.join(format!("{}.png", logo.id)) // Теперь с расширением .png
-        };
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        return Err(FetchError::Status(status, retry_after));
+    }
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+    };
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    let content_type = header(CONTENT_TYPE);
+    let body = response
+        .bytes()
+        .await
+        .map_err(FetchError::Network)?
+        .to_vec();
 
-        let bytes = response.bytes().await?;
+    let fetched = CachedResponse {
+        url: url.to_string(),
+        etag,
+        last_modified,
+        content_type,
+        body,
+    };
+    if let Err(e) = downloader.cache.store(&fetched).await {
+        warn!("Не удалось сохранить '{url}' в кэш: {e}");
+    }
 
-        if is_svg {
-            // Для SVG просто сохраняем как есть
+    Ok(Fetched {
+        status,
+        content_type: fetched.content_type,
+        body: fetched.body,
+    })
+}
+
+/// Сохранение скачанного файла. Формат определяется по содержимому:
+/// ссылка и Content-Type часто врут (картинка без расширения, SVG как text/plain,
+/// страница входа вместо картинки)
+async fn store_logo(
+    id: u32,
+    content_type: Option<String>,
+    bytes: &[u8],
+    svg_mode: SvgMode,
+    folders: &LogoFolders,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    // Контракт хранения:
+    // - растровые: download/<id>.png
+    // - svg: rework/<id>.svg
+    // - svg в режиме rasterize: download/<id>.png и исходник download/<id>.svg
+    // - svg в режиме vector: готовый result/<id>.svg
+    match sniff_content(bytes) {
+        ContentKind::Svg => {
+            // SVG с сайта попадает в продукт: скрипты и внешние ссылки удаляются до сохранения
+            let bytes = &sanitize_svg_bytes(bytes)?;
+            let stored = match svg_mode {
+                SvgMode::Rework => None,
+                SvgMode::Rasterize => Some(store_rasterized_svg(id, bytes, &folders.download)),
+                SvgMode::Vector => Some(store_vector_svg(id, bytes, &folders.result)),
+            };
+            match stored {
+                Some(Ok(out_path)) => return Ok(out_path),
+                // Неподдерживаемый SVG остаётся для ручной доработки
+                Some(Err(e)) => {
+                    warn!("Логотип {id}: SVG не обработан, сохранён для доработки: {e}")
+                }
+                None => {}
+            }
+            let out_path = folders.rework.join(format!("{}.svg", id));
             let mut file = File::create(&out_path).await?;
-            file.write_all(&bytes).await?;
-        } else {
+            file.write_all(bytes).await?;
+            Ok(out_path)
+        }
+        ContentKind::Raster(format) => {
+            let out_path = folders.download.join(format!("{}.png", id));
             // Для растровых изображений конвертируем в PNG
-            let img = image::load_from_memory(&bytes)
-                .map_err(|e| format!("Ошибка загрузки изображения: {}", e))?;
-            // Сохраняем в PNG
+            let img = image::load_from_memory_with_format(bytes, format)
+                .map_err(|e| ContentError::Decode(e.to_string()))?;
             img.save_with_format(&out_path, ImageFormat::Png)
                 .map_err(|e| format!("Ошибка сохранения PNG: {}", e))?;
+            Ok(out_path)
         }
+        kind => Err(content_error(kind, bytes, content_type).into()),
+    }
+}
 
-        info!(
-            "{idx} Файл '{}' -> {} успешно скачан",
-            logo.url,
-            out_path.display()
-        );
-    } else {
-        let status = response.status();
-        let msg = format!("Ошибка загрузки '{}'. Код статуса: {}", logo.url, status);
-        error!("{msg}");
-        return Err(msg.into());
+/// Отрисовка SVG в растр для обработки вместе с остальными логотипами.
+/// Исходник без собственного фона сохраняется рядом: его пути попадут в итоговый SVG
+fn store_rasterized_svg(
+    id: u32,
+    bytes: &[u8],
+    download_folder: &Path,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let image = rasterize_svg(&parse_svg(bytes)?)?;
+    if has_transparent_background(&image) {
+        std::fs::write(download_folder.join(format!("{}.svg", id)), bytes)?;
     }
 
-    Ok(())
+    let out_path = download_folder.join(format!("{}.png", id));
+    flatten_on_white(&image)
+        .save_with_format(&out_path, ImageFormat::Png)
+        .map_err(|e| format!("Ошибка сохранения PNG: {}", e))?;
+    Ok(out_path)
+}
+
+/// Итоговый логотип сразу из путей SVG: без растра, векторизации и апскейла.
+/// Цвет фона подбирается так же, как для растровых логотипов
+fn store_vector_svg(
+    id: u32,
+    bytes: &[u8],
+    result_folder: &Path,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let tree = parse_svg(bytes)?;
+    let image = DynamicImage::ImageRgba8(flatten_on_white(&rasterize_svg(&tree)?));
+    let background = result_background(
+        DominantColor::from_rgba_image(image.to_rgb8())?,
+        WHITE_BG_REPLACE_GRAY,
+    );
+    let tree = strip_background(bytes, &tree).unwrap_or(tree);
+
+    let out_path = result_folder.join(format!("{}.svg", id));
+    save_source_vector_logo(&tree, id, background, &out_path)?;
+    Ok(out_path)
 }
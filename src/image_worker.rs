@@ -1,12 +1,13 @@
 use crate::background_works::{trim_transparent_border, DominantColor};
 use crate::config::Config;
 use crate::job_loaders::{Jobs, LogoJob};
-use crate::svg_saver::save_ready_logo;
+use crate::svg_render::parse_svg;
+use crate::svg_saver::{save_ready_logo, save_source_vector_logo};
 
 use futures::stream::{self, StreamExt};
-use image::{DynamicImage, GenericImageView, ImageBuffer, ImageReader, Rgb, Rgba};
+use image::{DynamicImage, GenericImageView, ImageBuffer, ImageReader, Rgb, Rgba, RgbaImage};
 use indicatif::ProgressBar;
-use log::{error, info};
+use log::{error, info, warn};
 use palette::Srgb;
 use std::env;
 use std::error::Error;
@@ -19,7 +20,11 @@ use std::process::Command;
 const WHITE_COLOR: u8 = 250;
 const MIN_SCORE_DOMINANT_COLOR: f32 = 0.5;
 const GRAY_BACKGROUND_COLOR: Srgb<u8> = Srgb::new(238, 237, 241);
+// Замена белого фона результата серым, общая для растровых и векторных логотипов
+pub const WHITE_BG_REPLACE_GRAY: bool = false;
 const PROCESS_CONCURRENCY: usize = 16;
+// Радиус скругления иконки приложения относительно стороны (маска iOS)
+const APP_ICON_CORNER_RATIO: f32 = 0.2237;
 
 async fn process_logos_concurrently<F, Fut>(
     logos: &[LogoJob],
@@ -136,7 +141,7 @@ pub async fn images_works_parallel(
                     &download_folder,
                     &upscale_folder,
                     &result_folder,
-                    false,
+                    WHITE_BG_REPLACE_GRAY,
                 )
                 .await;
                 bar.inc(1);
@@ -164,6 +169,19 @@ fn has_alpha_channel(img: &DynamicImage) -> bool {
     )
 }
 
+/// Цвет фона результата: серый для белого фона при `white_bg_replace_gray`
+/// и доминантный для остальных
+pub fn result_background(background: DominantColor, white_bg_replace_gray: bool) -> DominantColor {
+    if white_bg_replace_gray && background.average > WHITE_COLOR {
+        DominantColor {
+            color: GRAY_BACKGROUND_COLOR,
+            ..background
+        }
+    } else {
+        background
+    }
+}
+
 async fn process_single_logo(
     logo: LogoJob,
     task: i32,
@@ -181,15 +199,24 @@ async fn process_single_logo(
     // Загружаем изображения
     let small_image = load_image(&small_image_name)?;
     let has_alpha = has_alpha_channel(&small_image);
+    let mut small_image = small_image.to_rgba8();
     let mut final_image = load_image(&big_image_name)?.to_rgba8();
 
+    // Иконка приложения приходит скруглённым квадратом: углы заливаются цветом плитки,
+    // чтобы фон определялся по всей плитке и удалялся вместе с углами
+    if logo.app_icon {
+        fill_app_icon_corners(&mut small_image);
+        fill_app_icon_corners(&mut final_image);
+    }
+
     info!(
         "Таска {task} обработки начата. Задача:{id} Файлы для обработки: {} {}",
         small_image_name.display(),
         big_image_name.display()
     );
 
-    let background = DominantColor::from_rgba_image(small_image.to_rgb8())?;
+    let background =
+        DominantColor::from_rgba_image(DynamicImage::ImageRgba8(small_image).to_rgb8())?;
     if background.score > MIN_SCORE_DOMINANT_COLOR {
         background.remove_image_background(&mut final_image);
         final_image = trim_transparent_border(&mut final_image);
@@ -208,15 +235,7 @@ async fn process_single_logo(
     //     DominantColor::white()
     // };
 
-    // Выбор цвета фона серый для белого фона и доминантный для остальных
-    let background = if white_bg_replace_gray && background.average > WHITE_COLOR {
-        DominantColor {
-            color: GRAY_BACKGROUND_COLOR,
-            ..background
-        }
-    } else {
-        background
-    };
+    let background = result_background(background, white_bg_replace_gray);
 
     // Формирование имени SVG файла
     let new_image_name = result_folder.join(format!("{}.svg", id));
@@ -233,8 +252,26 @@ async fn process_single_logo(
         background.k
     );
 
-    // Создание SVG
-    save_ready_logo(final_image, id, background, &new_image_name, true)?;
+    // Создание SVG. Для отрисованного SVG без фона берутся его исходные пути
+    let source_svg = download_folder.join(format!("{}.svg", id));
+    let source_tree = if source_svg.exists() {
+        fs::read(&source_svg)
+            .map_err(|e| e.into())
+            .and_then(|bytes| parse_svg(&bytes))
+            .inspect_err(|e| {
+                warn!(
+                    "{}: исходный SVG не используется: {e}",
+                    source_svg.display()
+                )
+            })
+            .ok()
+    } else {
+        None
+    };
+    match source_tree {
+        Some(tree) => save_source_vector_logo(&tree, id, background, &new_image_name)?,
+        None => save_ready_logo(final_image, id, background, &new_image_name, true)?,
+    }
 
     info!(
         "{} Таска закончена. Задача:{} Файлы для обработки: {} {} Сохранение {}",
@@ -247,6 +284,26 @@ async fn process_single_logo(
     Ok(())
 }
 
+/// Заливает углы вне скруглённого квадрата цветом середины верхнего края плитки
+fn fill_app_icon_corners(image: &mut RgbaImage) {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return;
+    }
+    let tile = *image.get_pixel(width / 2, 0);
+    let radius = width.min(height) as f32 * APP_ICON_CORNER_RATIO;
+    let (right, bottom) = (width as f32 - radius, height as f32 - radius);
+
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        let dx = px - px.clamp(radius, right);
+        let dy = py - py.clamp(radius, bottom);
+        if dx * dx + dy * dy > radius * radius {
+            *pixel = tile;
+        }
+    }
+}
+
 /// Расширения форматов, поддерживаемых ImageReader при включённых фичах крейта image.
 
 /// Загружает изображение из файла. В `image_name` передаётся путь без расширения —
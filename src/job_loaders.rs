@@ -1,19 +1,65 @@
-use crate::otp::AuthenticationService;
+use crate::claims::LogoClaims;
+use crate::config::Config;
+use crate::otp::{AuthenticationService, User};
+use crate::otp_qr::otp_qr_terminal;
+use crate::page_extractors::page_client;
 use crate::parsers::{Data, Root};
-use reqwest::Client;
+use futures::stream::{self, StreamExt};
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+const ADVISA_URL: &str = "https://app.advisa.ru/master";
+const RESOLVE_CONCURRENCY: usize = 16;
+
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct LogoJob {
     pub url: String,
     pub id: u32,
+    /// Источник — иконка приложения из магазина (обычно скруглённый квадрат)
+    #[serde(default)]
+    pub app_icon: bool,
+    /// Запасные ссылки, если основная не скачалась или не читается как картинка
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_urls: Vec<String>,
+    /// Встроенный в страницу SVG, сохранённый при разборе ссылок. Берётся вместо скачивания `url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inline_svg: Option<PathBuf>,
 }
 
 impl LogoJob {
     pub fn new(id: u32, url: String) -> Self {
-        Self { id, url }
+        Self {
+            id,
+            url,
+            app_icon: false,
+            fallback_urls: Vec::new(),
+            inline_svg: None,
+        }
+    }
+
+    pub fn new_app_icon(id: u32, url: String) -> Self {
+        Self {
+            id,
+            url,
+            app_icon: true,
+            fallback_urls: Vec::new(),
+            inline_svg: None,
+        }
+    }
+
+    /// Задание на встроенный SVG страницы `url`, сохранённый в файл `path`
+    pub fn new_inline_svg(id: u32, url: String, path: PathBuf) -> Self {
+        Self {
+            inline_svg: Some(path),
+            ..Self::new(id, url)
+        }
+    }
+
+    pub fn with_fallback_urls(mut self, fallback_urls: Vec<String>) -> Self {
+        self.fallback_urls = fallback_urls;
+        self
     }
 }
 
@@ -77,78 +123,135 @@ impl Jobs {
         Ok(Jobs { logos })
     }
 
+    /// Переносит признаки источника логотипа из исходного задания по id.
+    /// Нужно после пересоздания задания по файлам директории
+    pub fn with_sources_from(mut self, source: &Jobs) -> Self {
+        for logo in &mut self.logos {
+            if let Some(original) = source.logos.iter().find(|x| x.id == logo.id) {
+                logo.app_icon = original.app_icon;
+            }
+        }
+        self
+    }
+
     /// Загрузка задачи по созданию логотипов
-    pub fn load_json_job(
+    pub async fn load_json_job(
         json_text: &str,
-        json_file_path: &str,
-        temp_job_path: &PathBuf,
+        config: &Config,
         backup: bool,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        println!("Скачка файла {}", json_file_path);
+        println!("Скачка файла {}", config.job());
         let json_content = if json_text.is_empty() {
-            fs::read_to_string(json_file_path)?
+            fs::read_to_string(config.job())?
         } else {
             json_text.to_string()
         };
 
         let root: Root = serde_json::from_str::<Root>(&json_content)?;
-        let jobs = Self::json_to_jobs(&root.data);
+        let jobs = Self::json_to_jobs(&root.data, config).await?;
         // Сохранить задачу на всякий случай
 
         if backup {
-            jobs.jobs_backup(temp_job_path)?;
+            jobs.jobs_backup(&config.temp_job_file())?;
         }
         Ok(jobs)
     }
 
-    fn json_to_jobs(root: &Data) -> Jobs {
-        let logos: Vec<LogoJob> = root
-            .data
-            .iter()
-            .filter_map(|x| x.get_job().ok())
-            .flatten()
-            .collect();
+    /// Определение ссылок на логотипы: из вложений, а при их отсутствии со страницы из заметки.
+    /// Заявки обрабатываются параллельно с сохранением исходного порядка
+    async fn json_to_jobs(
+        root: &Data,
+        config: &Config,
+    ) -> Result<Jobs, Box<dyn std::error::Error + Send + Sync>> {
+        let client = page_client(config)?;
+        let client = &client;
+        let logos: Vec<LogoJob> = stream::iter(root.data.iter().cloned())
+            .map(|item| async move {
+                item.resolve_job(client, config).await.unwrap_or_else(|e| {
+                    log::warn!("Заявка {}: ссылка на логотип не найдена: {}", item.id, e);
+                    None
+                })
+            })
+            .buffered(RESOLVE_CONCURRENCY)
+            .filter_map(|job| async move { job })
+            .collect()
+            .await;
 
         println!("Обнаружено заданий {}", logos.len());
 
-        let jobs = Jobs { logos };
-        jobs
+        Ok(Jobs { logos })
     }
 
+    /// Загрузка заданий с сервера ADVISA. Если сервер вернул ссылку подключения
+    /// аутентификатора, она передаётся в `on_otp_url`, иначе QR код выводится в терминал
     pub async fn load_from_server(
         login: &str,
         password: &str,
         otp_code: Option<String>,
+        on_otp_url: Option<&(dyn Fn(&str) + Send + Sync)>,
+        config: &Config,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        let auth_url = "https://app.advisa.ru/master";
-        let auth_service = AuthenticationService::new(auth_url);
+        Self::server_login(login, password, otp_code, on_otp_url)
+            .await?
+            .load_jobs(config)
+            .await
+    }
 
-        println!("Авторизация на ADVISA");
+    /// Загрузка заданий с сервера ADVISA с захватом заявок, чтобы другие операторы
+    /// их пропустили. Заявки, захваченные кем-то другим, в задание не попадают
+    pub async fn load_from_server_claimed(
+        login: &str,
+        password: &str,
+        otp_code: Option<String>,
+        on_otp_url: Option<&(dyn Fn(&str) + Send + Sync)>,
+        config: &Config,
+    ) -> Result<(Self, LogoClaims), Box<dyn std::error::Error + Send + Sync>> {
+        Self::server_login(login, password, otp_code, on_otp_url)
+            .await?
+            .load_jobs_claimed(config)
+            .await
+    }
+
+    /// Авторизация на сервере ADVISA с вводом одноразового кода из консоли,
+    /// если он не передан заранее
+    async fn server_login(
+        login: &str,
+        password: &str,
+        otp_code: Option<String>,
+        on_otp_url: Option<&(dyn Fn(&str) + Send + Sync)>,
+    ) -> Result<ServerSession, Box<dyn std::error::Error + Send + Sync>> {
+        let session = ServerSession::login(login, password).await?;
 
-        // Логин
-        match auth_service.login(&login, &password).await {
-            Ok(_) => {
-                println!("Успешный логин. Пожалуйста введите одноразовый код:");
-
-                let code = otp_code.unwrap_or_else(|| {
-                    let mut otp_code = String::new();
-                    std::io::stdin()
-                        .read_line(&mut otp_code)
-                        .expect("Ошибка ввода одноразового пароля");
-                    otp_code.trim().to_string()
-                });
-
-                // OTP логин
-                if auth_service.is_otp_required().unwrap_or(false) {
-                    match auth_service.login_otp(code.as_str()).await {
-                        Ok(_) => println!("OTP успешный"),
-                        Err(e) => println!("Ошибка OTP пароля: {}", e.message()),
+        if session.otp_required() {
+            if let Some(otp_url) = session.otp_url() {
+                match on_otp_url {
+                    Some(handler) => handler(&otp_url),
+                    None => {
+                        println!("Отсканируйте QR код приложением-аутентификатором:");
+                        println!("{}", otp_qr_terminal(&otp_url)?);
                     }
                 }
             }
-            Err(e) => println!("Ошибка логина: {}", e.message),
+
+            println!("Успешный логин. Пожалуйста введите одноразовый код:");
+            let code = otp_code.unwrap_or_else(|| {
+                let mut otp_code = String::new();
+                std::io::stdin()
+                    .read_line(&mut otp_code)
+                    .expect("Ошибка ввода одноразового пароля");
+                otp_code.trim().to_string()
+            });
+            session.submit_otp(&code).await?;
         }
 
+        Ok(session)
+    }
+
+    /// Получение списка открытых заявок на логотипы
+    async fn fetch_server_jobs(
+        auth_service: &AuthenticationService,
+        config: &Config,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         #[derive(Debug, Serialize, Deserialize)]
         struct LogoRequest {
             count: i32,
@@ -161,7 +264,7 @@ impl Jobs {
         }
 
         // URL для запроса
-        let url = "https://app.advisa.ru/master/service/logoRequest/list";
+        let url = format!("{ADVISA_URL}/service/logoRequest/list");
 
         // Формируем тело запроса
         let request_body = LogoRequest {
@@ -177,7 +280,7 @@ impl Jobs {
         // Отправляем POST запрос
         let response = auth_service
             .http_client
-            .post(url)
+            .post(&url)
             .json(&request_body)
             .send()
             .await?;
@@ -190,8 +293,17 @@ impl Jobs {
 
             let data: Data = serde_json::from_str::<Data>(&response_text)?;
             // println!("data: {data:?}");
-            let jobs = Self::json_to_jobs(&data);
+            let jobs = Self::json_to_jobs(&data, config).await?;
             Ok(jobs)
+        } else if matches!(
+            response.status(),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+        ) {
+            Err(format!(
+                "Сервер отказал в доступе к заявкам на логотипы ({}): у пользователя нет разрешения на список заявок",
+                response.status()
+            )
+            .into())
         } else {
             println!("Ошибка запроса: {}", response.status());
             let error_text = response.text().await?;
@@ -201,9 +313,127 @@ impl Jobs {
     }
 
     /// Сохраняет список заданий в JSON по указанному пути (резервная копия).
-    fn jobs_backup(&self, path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    pub fn jobs_backup(&self, path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let json = serde_json::to_string_pretty(&self.logos)?;
         fs::write(path, json)?;
         Ok(())
     }
 }
+
+/// Сессия на сервере ADVISA между вводом пароля и одноразового кода.
+/// GUI показывает QR код подключения аутентификатора после `login`
+/// и только затем запрашивает код для `submit_otp`
+pub struct ServerSession {
+    auth_service: AuthenticationService,
+}
+
+impl ServerSession {
+    /// Первый шаг авторизации: логин и пароль
+    pub async fn login(
+        login: &str,
+        password: &str,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let auth_service = AuthenticationService::new(ADVISA_URL);
+
+        println!("Авторизация на ADVISA");
+        auth_service
+            .login(login, password)
+            .await
+            .map_err(|e| format!("Ошибка логина: {}", e.message()))?;
+
+        Ok(Self { auth_service })
+    }
+
+    /// Сервер ждёт одноразовый код
+    pub fn otp_required(&self) -> bool {
+        self.auth_service.is_otp_required().unwrap_or(false)
+    }
+
+    /// Ссылка подключения аутентификатора (`otpauth://...`), если сервер её прислал
+    pub fn otp_url(&self) -> Option<String> {
+        let otp_url = self.auth_service.get_otp_url();
+        if otp_url.is_empty() {
+            None
+        } else {
+            log::info!("Получена ссылка подключения аутентификатора");
+            Some(otp_url)
+        }
+    }
+
+    /// Второй шаг авторизации: одноразовый код из аутентификатора
+    pub async fn submit_otp(
+        &self,
+        code: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.auth_service
+            .login_otp(code.trim())
+            .await
+            .map_err(|e| format!("Ошибка OTP пароля: {}", e.message()))?;
+        println!("OTP успешный");
+        Ok(())
+    }
+
+    /// Загрузка открытых заявок после проверки прав пользователя
+    pub async fn load_jobs(
+        &self,
+        config: &Config,
+    ) -> Result<Jobs, Box<dyn std::error::Error + Send + Sync>> {
+        self.checked_user(config)?;
+        Jobs::fetch_server_jobs(&self.auth_service, config).await
+    }
+
+    /// Загрузка открытых заявок с захватом, чтобы другие операторы их пропустили
+    pub async fn load_jobs_claimed(
+        &self,
+        config: &Config,
+    ) -> Result<(Jobs, LogoClaims), Box<dyn std::error::Error + Send + Sync>> {
+        let user = self.checked_user(config)?;
+        let jobs = Jobs::fetch_server_jobs(&self.auth_service, config).await?;
+
+        let mut claims = LogoClaims::new(
+            self.auth_service.http_client.clone(),
+            ADVISA_URL,
+            user.name(),
+            &config.claim_file(),
+            config.claim_mode(),
+        );
+        let jobs = claims.claim(jobs).await?;
+        Ok((jobs, claims))
+    }
+
+    /// Данные пользователя, полученные при авторизации, и проверка его прав
+    fn checked_user(
+        &self,
+        config: &Config,
+    ) -> Result<User, Box<dyn std::error::Error + Send + Sync>> {
+        let user = self
+            .auth_service
+            .user()
+            .ok_or("Не удалось получить данные пользователя после авторизации")?;
+        println!(
+            "Активный пользователь: {} Банк: {}",
+            user.name(),
+            user.bank_id()
+        );
+        log::info!(
+            "Активный пользователь: {} Банк: {} Разрешения: {:?}",
+            user.name(),
+            user.bank_id(),
+            user.permissions()
+        );
+        let required = config.logo_permissions();
+        if required.is_empty() {
+            // Без списка разрешений клиент ничего не отсекает, остаётся только отказ сервера
+            let warning = format!(
+                "ВНИМАНИЕ: не заданы разрешения на список и обновление заявок (--logo-permissions), \
+                 права пользователя {} не проверяются. Разрешения пользователя: {:?}",
+                user.name(),
+                user.permissions()
+            );
+            println!("{warning}");
+            log::warn!("{warning}");
+        }
+        user.check_logo_request_access(&required)?;
+        Ok(user)
+    }
+}
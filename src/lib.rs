@@ -1,19 +1,49 @@
 mod background_works;
+mod cdn_urls;
+mod claims;
 mod config;
+mod content_sniff;
+mod http_cache;
 mod image_loader;
 mod image_worker;
 mod job_loaders;
 mod logger;
 mod otp;
+mod otp_qr;
+mod page_extractors;
 mod parsers;
+mod site_rules;
+mod svg_render;
+mod svg_sanitizer;
 mod svg_saver;
 mod vectorize;
+mod well_known;
 
-pub use config::Config;
+pub use claims::LogoClaims;
+pub use config::{ClaimMode, Config, SvgMode};
 pub use image_loader::download_images;
 pub use image_worker::{images_works_parallel, remove_border_parallel, upscale_images};
-pub use job_loaders::{Jobs, LogoJob};
+pub use job_loaders::{Jobs, LogoJob, ServerSession};
 pub use logger::setup_logger;
+pub use otp_qr::{otp_qr_png_base64, otp_qr_svg, otp_qr_terminal};
+
+/// Внутренние функции для интеграционных тестов в `tests/`, не часть API крейта
+#[doc(hidden)]
+pub mod test_support {
+    pub use crate::cdn_urls::upgrade_cdn_url;
+    pub use crate::content_sniff::{sniff_content, ContentKind};
+    pub use crate::image_loader::{RetryPolicy, RETRY_BASE_DELAY, RETRY_MAX_DELAY};
+    pub use crate::logger::redact;
+    pub use crate::page_extractors::{
+        appstore_app_id, appstore_icon, appstore_lookup_icon, decode_html, google_play_icon,
+        hh_employer_id, hh_employer_link, hh_employer_logo, is_short_link, join_http_url,
+        meta_refresh_target, store_page_icon, telegram_avatar, vk_avatar, yandex_org_logo,
+    };
+    pub use crate::parsers::{rank_page_icons, UrlType};
+    pub use crate::site_rules::SiteRules;
+    pub use crate::svg_render::{parse_svg, strip_background};
+    pub use crate::svg_sanitizer::sanitize_svg;
+}
 
 pub fn create_dir(dir: &std::path::Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     if !dir.exists() {
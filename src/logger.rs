@@ -1,14 +1,56 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::borrow::Cow;
+
+// Шаблоны секретов (пароли, OTP коды, токены, сессионные cookies) и их замены
+static SECRET_PATTERNS: Lazy<Vec<(Regex, &str)>> = Lazy::new(|| {
+    [
+        // Query string и form body: password=..., code=..., secret=...
+        (
+            r"(?i)(\b(?:password|passwd|pwd|code|otp|otp_code|secret|token|access_token)=)[^&\s;,]+",
+            "${1}***",
+        ),
+        // JSON и Debug вывод структур: "password": "...", code: "..."
+        (
+            r#"(?i)("?\b(?:password|passwd|code|otp|otp_code|otpCode|secret|token)"?\s*:\s*)"[^"]*""#,
+            r#"${1}"***""#,
+        ),
+        // Сессионные cookies
+        (
+            r"(?i)(\b(?:JSESSIONID|SESSION|SESSIONID|SID|XSRF-TOKEN)=)[^;\s&,]+",
+            "${1}***",
+        ),
+        // Заголовки Cookie / Set-Cookie целиком
+        (r"(?i)(\b(?:set-cookie|cookie)\s*:\s*)[^\r\n]+", "${1}***"),
+    ]
+    .into_iter()
+    .map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), replacement))
+    .collect()
+});
+
+/// Маскирует пароли, OTP коды и сессионные cookies в строке перед выводом в лог
+pub fn redact(message: &str) -> Cow<'_, str> {
+    let mut result = Cow::Borrowed(message);
+    for (pattern, replacement) in SECRET_PATTERNS.iter() {
+        if pattern.is_match(&result) {
+            result = Cow::Owned(pattern.replace_all(&result, *replacement).into_owned());
+        }
+    }
+    result
+}
+
 pub fn setup_logger(
     log_file: &std::path::Path,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     fern::Dispatch::new()
+        // Маскирование выполняется в общем форматтере, поэтому действует на все подключенные выводы
         .format(|out, message, record| {
             out.finish(format_args!(
                 "[{} {} {}] {}",
                 chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
                 record.level(),
                 record.target(),
-                message
+                redact(&message.to_string())
             ))
         })
         .level(log::LevelFilter::Info)
@@ -12,18 +12,49 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     println!("Инициализация лога");
     setup_logger(&config.log_file())?;
 
+    // Offline: задание прошлого запуска, картинки из кэша
+    if config.offline() {
+        let logos = Jobs::load_database_json_job(&config.temp_job_file().display().to_string())?;
+        return process_logos(&logos, &config).await;
+    }
+
     let login = std::env::var("login").expect("Environment variable 'login' not set");
     let password = std::env::var("password").expect("Environment variable 'password' not set");
 
-    let logos = Jobs::load_from_server(login.as_str(), password.as_str(), None).await?;
+    let (logos, mut claims) = if config.claim() {
+        let (logos, claims) =
+            Jobs::load_from_server_claimed(login.as_str(), password.as_str(), None, None, &config)
+                .await?;
+        (logos, Some(claims))
+    } else {
+        let logos =
+            Jobs::load_from_server(login.as_str(), password.as_str(), None, None, &config).await?;
+        (logos, None)
+    };
     // println!("Скачка задания {}", Path::new(config.job()).display());
-    // let logos = Jobs::load_json_job("", config.job(), &config.temp_job_file(), true)?;
+    // let logos = Jobs::load_json_job("", &config, true).await?;
 
     if logos.logos.is_empty() {
         println!("Нет заданий");
         return Ok(());
     }
+    // Сохранить задание для повторной обработки в режиме offline
+    if let Err(e) = logos.jobs_backup(&config.temp_job_file()) {
+        log::warn!("Не удалось сохранить задание для режима offline: {e}");
+    }
+
+    let result = process_logos(&logos, &config).await;
+
+    // Заявки упавшего запуска возвращаем в очередь для других операторов
+    if result.is_err() {
+        if let Some(claims) = claims.as_mut() {
+            claims.release().await;
+        }
+    }
+    result
+}
 
+async fn process_logos(logos: &Jobs, config: &Config) -> Result<(), Box<dyn Error + Send + Sync>> {
     for folder in config.all_folders() {
         delete_dir(&folder)?;
     }
@@ -34,18 +65,19 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     }
 
     if config.download() {
-        download_images(&logos, &config).await?;
+        download_images(logos, config).await?;
     }
 
     let logos =
-        Jobs::generate_job_from_dir_images(&config.download_folder().display().to_string())?;
+        Jobs::generate_job_from_dir_images(&config.download_folder().display().to_string())?
+            .with_sources_from(logos);
 
-    remove_border_parallel(&logos, &config).await?;
+    remove_border_parallel(&logos, config).await?;
 
     if config.upscale() {
-        upscale_images(&config).await?;
+        upscale_images(config).await?;
     }
-    images_works_parallel(&logos, &config).await?;
+    images_works_parallel(&logos, config).await?;
 
     Ok(())
 }
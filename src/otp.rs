@@ -1,4 +1,5 @@
-use reqwest::{Client, Error as ReqwestError, StatusCode};
+use crate::logger::redact;
+use reqwest::{Client, Error as ReqwestError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -12,6 +13,48 @@ pub struct User {
     bank_id: i32,
 }
 
+impl User {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn bank_id(&self) -> i32 {
+        self.bank_id
+    }
+
+    pub fn permissions(&self) -> &[Permission] {
+        &self.permissions
+    }
+
+    /// Администратор имеет все разрешения
+    pub fn has_permission(&self, identifier: &str) -> bool {
+        self.permissions
+            .iter()
+            .any(|p| p.identifier() == identifier || *p == Permission::Admin)
+    }
+
+    /// Проверка прав на получение и обновление заявок на логотипы.
+    /// `required` — идентификаторы разрешений сервера из конфигурации
+    pub fn check_logo_request_access(&self, required: &[String]) -> Result<(), String> {
+        let missing: Vec<&str> = required
+            .iter()
+            .map(String::as_str)
+            .filter(|p| !self.has_permission(p))
+            .collect();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "У пользователя {} (банк {}) нет разрешений на работу с заявками на логотипы: {}",
+                self.name,
+                self.bank_id,
+                missing.join(", ")
+            ))
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum Permission {
@@ -19,7 +62,22 @@ pub enum Permission {
     Admin,
     User,
     Manager,
-    // ... другие разрешения
+    // Остальные разрешения сервера сохраняются как есть: их идентификаторы
+    // не описаны в клиенте и задаются в конфигурации (logo_permissions)
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl Permission {
+    /// Идентификатор разрешения в ответе сервера
+    pub fn identifier(&self) -> &str {
+        match self {
+            Permission::Admin => "admin",
+            Permission::User => "user",
+            Permission::Manager => "manager",
+            Permission::Other(identifier) => identifier,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -97,6 +155,10 @@ impl AuthenticationService {
             .map(|u| u.name.clone())
             .unwrap_or_default()
     }
+    pub fn user(&self) -> Option<User> {
+        self.user.read().unwrap().clone()
+    }
+
     pub fn is_logged(&self) -> bool {
         self.user.read().unwrap().is_some()
     }
@@ -133,32 +195,16 @@ impl AuthenticationService {
     }
 
     pub async fn login(&self, username: &str, password: &str) -> Result<(), AuthenticationError> {
-        let url = format!(
-            "{}?username={}&password={}",
-            self.login_url,
-            urlencoding::encode(username),
-            urlencoding::encode(password)
-        );
-
-        println!("Sending request to: {}", url);
+        println!("Sending request to: {}", self.login_url);
 
-        match self
-            .http_client
-            .post(&url)
-            .header(reqwest::header::USER_AGENT, "MyTauriApp/1.0")
-            // .header(reqwest::header::USER_AGENT, "Mozilla/5.0 (Tauri App)")
-            // .header(reqwest::header::CONTENT_TYPE, "application/json")
-            .json(&serde_json::json!({}))
-            .send()
-            .await
-        {
+        match self.send_credentials(username, password).await {
             Ok(response) => {
                 let status = response.status();
                 if status.is_success() {
                     match response.json::<LoginResult>().await {
                         Ok(result) => {
-                            println!("Login result {:?}", result);
-                            self.login_success(result);
+                            println!("Login result {}", redact(&format!("{:?}", result)));
+                            self.login_success(result).await;
                             Ok(())
                         }
                         Err(e) => {
@@ -180,11 +226,33 @@ impl AuthenticationService {
         }
     }
 
+    /// Отправка логина и пароля в теле запроса (form). Пароль не попадает в URL,
+    /// поэтому не виден в логах прокси и сервера
+    async fn send_credentials(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<reqwest::Response, ReqwestError> {
+        self.http_client
+            .post(&self.login_url)
+            .header(reqwest::header::USER_AGENT, "MyTauriApp/1.0")
+            .form(&[("username", username), ("password", password)])
+            .send()
+            .await
+    }
+
     pub async fn login_otp(&self, code: &str) -> Result<(), AuthenticationError> {
-        let url = format!("{}?code={}", self.otp_login_url, urlencoding::encode(code));
-        println!("Otp POST {url}");
+        println!("Otp POST {}", self.otp_login_url);
 
-        match self.http_client.post(&url).send().await {
+        // Код передаётся в теле формы, как и пароль: адреса запросов попадают в логи прокси
+        match self
+            .http_client
+            .post(&self.otp_login_url)
+            .form(&[("code", code)])
+            .send()
+            .await
+            .map_err(ReqwestError::without_url)
+        {
             Ok(response) => {
                 if response.status().is_success() {
                     self.otp_login_success().await;
@@ -206,16 +274,12 @@ impl AuthenticationService {
         }
     }
 
-    fn login_success(&self, otp: LoginResult) {
+    async fn login_success(&self, otp: LoginResult) {
         *self.last_error.write().unwrap() = None;
 
         if !otp.otp_required {
             println!("Успешный логин");
-            // Здесь нужно вызвать tokio::spawn для асинхронного вызова
-            let service = self.clone();
-            tokio::spawn(async move {
-                service.get_user_info().await;
-            });
+            self.get_user_info().await;
         } else {
             println!("Запрос дополнительного Otp пароля");
             *self.otp.write().unwrap() = Some(otp);
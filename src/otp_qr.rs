@@ -0,0 +1,44 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use image::{DynamicImage, Luma};
+use qrcode::render::{svg, unicode};
+use qrcode::QrCode;
+use std::error::Error;
+
+const QR_IMAGE_SIZE: u32 = 256;
+
+/// QR код ссылки подключения аутентификатора для вывода в терминал блоками Unicode.
+/// Цвета инвертированы, чтобы код читался на тёмном фоне терминала
+pub fn otp_qr_terminal(otp_url: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let code = QrCode::new(otp_url.as_bytes())?;
+    Ok(code
+        .render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build())
+}
+
+/// QR код ссылки подключения аутентификатора в виде SVG для GUI
+pub fn otp_qr_svg(otp_url: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let code = QrCode::new(otp_url.as_bytes())?;
+    Ok(code
+        .render::<svg::Color>()
+        .min_dimensions(QR_IMAGE_SIZE, QR_IMAGE_SIZE)
+        .dark_color(svg::Color("#000000"))
+        .light_color(svg::Color("#ffffff"))
+        .build())
+}
+
+/// QR код ссылки подключения аутентификатора в виде PNG закодированного в Base64 для GUI
+pub fn otp_qr_png_base64(otp_url: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let code = QrCode::new(otp_url.as_bytes())?;
+    let image = code
+        .render::<Luma<u8>>()
+        .min_dimensions(QR_IMAGE_SIZE, QR_IMAGE_SIZE)
+        .build();
+
+    let mut png_bytes = Vec::new();
+    let mut cursor = std::io::Cursor::new(&mut png_bytes);
+    DynamicImage::ImageLuma8(image).write_to(&mut cursor, image::ImageFormat::Png)?;
+    Ok(BASE64.encode(&png_bytes))
+}
@@ -0,0 +1,654 @@
+use crate::config::Config;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Response, StatusCode};
+use scraper::{Html, Selector};
+use std::error::Error;
+use std::time::Duration;
+use url::Url;
+
+// Браузерный User-Agent: многие сайты отдают заглушку без него
+const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+// Сервисы сокращения ссылок, ссылки которых раскрываются до определения типа сайта
+const SHORT_LINK_HOSTS: &[&str] = &[
+    "clck.ru",
+    "bit.ly",
+    "goo.gl",
+    "tinyurl.com",
+    "t.co",
+    "vk.cc",
+    "u.to",
+    "cutt.ly",
+    "is.gd",
+    "ow.ly",
+    "rebrand.ly",
+];
+// Сколько байт начала страницы просматривать в поисках meta тега кодировки
+const META_PRESCAN_BYTES: usize = 4096;
+// Ограничение цепочки переходов meta refresh
+const MAX_META_REFRESH: usize = 5;
+
+// Картинка-заглушка t.me для каналов без аватара
+const TELEGRAM_DEFAULT_IMAGE: &str = "telegram.org/img/t_logo";
+
+/// Клиент для страниц и картинок при определении ссылок на логотипы.
+/// Таймауты из конфигурации не дают одному зависшему сайту остановить весь этап
+pub fn page_client(config: &Config) -> Result<Client, Box<dyn Error + Send + Sync>> {
+    Ok(Client::builder()
+        .user_agent(BROWSER_USER_AGENT)
+        .connect_timeout(Duration::from_secs(config.connect_timeout()))
+        .read_timeout(Duration::from_secs(config.read_timeout()))
+        .build()?)
+}
+
+/// Загрузка HTML страницы с декодированием из кодировки страницы
+pub async fn fetch_page(
+    client: &Client,
+    url: &str,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(format!("Ошибка загрузки страницы '{}': {}", url, response.status()).into());
+    }
+    let content_type = content_type(&response);
+    let bytes = response.bytes().await?;
+    Ok(decode_html(&bytes, content_type.as_deref()))
+}
+
+/// Декодирование HTML в кодировке из BOM, заголовка Content-Type или meta тега.
+/// Заявленный в заголовке UTF-8 не доверяется, если содержимое им не является:
+/// сервер часто отдаёт его по умолчанию для страниц в windows-1251
+pub fn decode_html(bytes: &[u8], content_type: Option<&str>) -> String {
+    let header_encoding = content_type
+        .and_then(charset_label)
+        .and_then(|label| Encoding::for_label(label.as_bytes()));
+
+    let encoding = Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or(header_encoding
+            .filter(|encoding| *encoding != UTF_8 || std::str::from_utf8(bytes).is_ok()))
+        .or_else(|| meta_charset(bytes))
+        .unwrap_or(UTF_8);
+
+    let (text, _, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        log::info!("Страница декодирована из {} с ошибками", encoding.name());
+    }
+    text.into_owned()
+}
+
+/// Кодировка из `<meta charset="...">` или `<meta http-equiv="Content-Type" content="...; charset=...">`
+/// в начале документа
+fn meta_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    static META_CHARSET_REGEX: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?\s*([a-z0-9_:.\-]+)"#).unwrap()
+    });
+
+    // Объявление кодировки обязано быть в первых 1024 байтах, берём с запасом
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(META_PRESCAN_BYTES)]);
+    let label = META_CHARSET_REGEX
+        .captures(&head)?
+        .get(1)?
+        .as_str()
+        .to_string();
+    let encoding = Encoding::for_label(label.as_bytes())?;
+    // UTF-16 в meta теге невозможен для ASCII совместимого документа
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        return Some(UTF_8);
+    }
+    Some(encoding)
+}
+
+/// Значение `charset` из заголовка Content-Type
+fn charset_label(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim().eq_ignore_ascii_case("charset").then(|| {
+            value
+                .trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string()
+        })
+    })
+}
+
+/// Загрузка картинки по ссылке
+pub async fn fetch_bytes(
+    client: &Client,
+    url: &str,
+) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    Ok(fetch_typed_bytes(client, url).await?.1)
+}
+
+/// Загрузка картинки по ссылке вместе с типом содержимого из заголовка ответа
+pub async fn fetch_typed_bytes(
+    client: &Client,
+    url: &str,
+) -> Result<(Option<String>, Vec<u8>), Box<dyn Error + Send + Sync>> {
+    let response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(format!("Ошибка загрузки '{}': {}", url, response.status()).into());
+    }
+    let content_type = content_type(&response);
+    Ok((content_type, response.bytes().await?.to_vec()))
+}
+
+/// Статус и тип содержимого по ссылке без загрузки тела (HEAD запрос)
+pub async fn fetch_head(
+    client: &Client,
+    url: &str,
+) -> Result<(StatusCode, Option<String>), Box<dyn Error + Send + Sync>> {
+    let response = client.head(url).send().await?;
+    Ok((response.status(), content_type(&response)))
+}
+
+fn content_type(response: &Response) -> Option<String> {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+/// Ссылка сервиса сокращения ссылок
+pub fn is_short_link(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| {
+        url.host_str()
+            .is_some_and(|host| SHORT_LINK_HOSTS.contains(&host.to_lowercase().as_str()))
+    })
+}
+
+/// Раскрытие короткой ссылки: переходы по редиректам и meta refresh.
+/// При ошибке возвращается исходная ссылка
+pub async fn expand_short_link(client: &Client, url: &str) -> String {
+    let mut current = url.to_string();
+
+    for _ in 0..MAX_META_REFRESH {
+        let response = match client.get(&current).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                log::warn!("Не удалось раскрыть ссылку {url}: {e}");
+                break;
+            }
+        };
+        let final_url = response.url().clone();
+        let is_html = content_type(&response)
+            .is_some_and(|content_type| content_type.to_lowercase().starts_with("text/html"));
+        current = final_url.to_string();
+        if !is_html {
+            break;
+        }
+
+        match response.text().await {
+            Ok(html) => match meta_refresh_target(&html, &final_url) {
+                Some(target) => current = target.to_string(),
+                None => break,
+            },
+            Err(_) => break,
+        }
+    }
+
+    if current != url {
+        log::info!("Короткая ссылка {url} -> {current}");
+    }
+    current
+}
+
+/// Адрес перехода из `<meta http-equiv="refresh" content="0; url=...">`
+pub fn meta_refresh_target(html: &str, base_url: &Url) -> Option<Url> {
+    static REFRESH_URL_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"(?i)^\s*\d*\s*[;,]?\s*url\s*=\s*['"]?([^'"]+)"#).unwrap());
+
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("meta[http-equiv]").ok()?;
+    document
+        .select(&selector)
+        .filter(|el| {
+            el.value()
+                .attr("http-equiv")
+                .is_some_and(|value| value.eq_ignore_ascii_case("refresh"))
+        })
+        .find_map(|el| {
+            let content = el.value().attr("content")?;
+            let target = REFRESH_URL_REGEX.captures(content)?.get(1)?.as_str().trim();
+            base_url.join(target).ok()
+        })
+}
+
+/// Ссылка со страницы относительно её адреса. Принимаются только http(s):
+/// `file:`, `data:`, `javascript:` и прочие схемы со страницы не скачиваются
+pub fn join_http_url(base_url: &Url, href: &str) -> Option<Url> {
+    base_url
+        .join(href.trim())
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Значение атрибута первого найденного элемента
+fn select_attr(document: &Html, selector: &str, attr: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    document
+        .select(&selector)
+        .filter_map(|el| el.value().attr(attr))
+        .map(str::trim)
+        .find(|value| !value.is_empty())
+        .map(String::from)
+}
+
+/// Дополняет ссылку без протокола (`//cdn...`) до https
+fn with_scheme(url: String) -> String {
+    match url.strip_prefix("//") {
+        Some(rest) => format!("https://{rest}"),
+        None => url,
+    }
+}
+
+/// Аватар канала или бота со страницы предпросмотра t.me.
+/// Картинка блока `tgme_page_photo_image` совпадает с `og:image`, но не обрезана для превью
+pub fn telegram_avatar(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+
+    select_attr(&document, "img.tgme_page_photo_image", "src")
+        .or_else(|| select_attr(&document, "meta[property='og:image']", "content"))
+        .filter(|url| !url.contains(TELEGRAM_DEFAULT_IMAGE))
+        .map(with_scheme)
+}
+
+// Заглушки VK для сообществ без аватара: /images/camera_200.png, /images/community_200.png
+static VK_PLACEHOLDER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"/images/(camera|community|deactivated)_\d+").unwrap());
+// Объект с аватарами в данных страницы до вложенного объекта или конца:
+// {"id":123,"screen_name":"name","photo_200":"https:\/\/sun9-..."}
+static VK_PHOTO_OBJECT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"\{[^{}]*"photo_(?:\d+|max_orig|max|big|base)"\s*:\s*"https?:[^{}]*"#).unwrap()
+});
+// Аватар в объекте: "photo_200":"https:\/\/sun9-..."
+static VK_PHOTO_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#""photo_(\d+|max_orig|max|big|base)"\s*:\s*"(https?:[^"]+)""#).unwrap()
+});
+static VK_SCREEN_NAME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""screen_name"\s*:\s*"([^"]+)""#).unwrap());
+static VK_ID_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#""id"\s*:\s*-?(\d+)"#).unwrap());
+// Адрес сообщества без короткого имени: vk.com/club123, vk.com/public123
+static VK_CLUB_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:club|public|event)(\d+)$").unwrap());
+// Размер картинки userapi.com в параметре ?size=200x200
+static VK_SIZE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[?&]size=(\d+)x(\d+)").unwrap());
+
+/// Аватар сообщества VK максимального размера из `og:image`, `srcset` аватара
+/// и объекта сообщества в данных страницы (`photo_200`, `photo_max_orig` ...).
+/// Аватары участников, авторов постов и комментариев в данных страницы не учитываются
+pub fn vk_avatar(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let mut candidates: Vec<(u32, String)> = Vec::new();
+
+    if let Some(url) = select_attr(&document, "meta[property='og:image']", "content") {
+        candidates.push((vk_image_size(&url).unwrap_or(0), url));
+    }
+
+    // srcset аватара: "url 1x, url 2x" или "url 100w, url 200w"
+    for selector in ["img.page_avatar_img", "img.AvatarRich__img"] {
+        if let Some(srcset) = select_attr(&document, selector, "srcset") {
+            candidates.extend(parse_srcset(&srcset));
+        }
+        if let Some(url) = select_attr(&document, selector, "src") {
+            candidates.push((vk_image_size(&url).unwrap_or(0), url));
+        }
+    }
+
+    if let Some(community) = vk_community_name(&document) {
+        let objects = VK_PHOTO_OBJECT_REGEX
+            .find_iter(html)
+            .map(|found| found.as_str())
+            .filter(|object| is_vk_community_object(object, &community));
+        for object in objects {
+            for caps in VK_PHOTO_REGEX.captures_iter(object) {
+                let size = match &caps[1] {
+                    "max_orig" => u32::MAX,
+                    "max" => u32::MAX - 1,
+                    "big" => 200,
+                    "base" => 100,
+                    number => number.parse().unwrap_or(0),
+                };
+                let url = caps[2].replace("\\/", "/").replace("\\u0026", "&");
+                candidates.push((size, url));
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter(|(_, url)| url.starts_with("http") && !VK_PLACEHOLDER_REGEX.is_match(url))
+        .max_by_key(|(size, _)| *size)
+        .map(|(_, url)| url)
+}
+
+/// Короткое имя сообщества из `og:url` или canonical ссылки: vk.com/<имя>
+fn vk_community_name(document: &Html) -> Option<String> {
+    let url = select_attr(document, "meta[property='og:url']", "content")
+        .or_else(|| select_attr(document, "link[rel='canonical']", "href"))?;
+    let url = Url::parse(&with_scheme(url)).ok()?;
+    let name = url.path_segments()?.find(|segment| !segment.is_empty())?;
+    Some(name.to_lowercase())
+}
+
+/// Объект данных страницы описывает само сообщество: совпадает короткое имя,
+/// а для адресов club123 — идентификатор
+fn is_vk_community_object(object: &str, community: &str) -> bool {
+    // У пользователей есть имя и фамилия, у сообществ — только название
+    if object.contains("\"first_name\"") {
+        return false;
+    }
+    if let Some(caps) = VK_SCREEN_NAME_REGEX.captures(object) {
+        return caps[1].eq_ignore_ascii_case(community);
+    }
+    match (
+        VK_CLUB_REGEX.captures(community),
+        VK_ID_REGEX.captures(object),
+    ) {
+        (Some(club), Some(id)) => club[1] == id[1],
+        _ => false,
+    }
+}
+
+fn vk_image_size(url: &str) -> Option<u32> {
+    VK_SIZE_REGEX
+        .captures(url)
+        .and_then(|caps| caps[1].parse::<u32>().ok().max(caps[2].parse::<u32>().ok()))
+}
+
+/// Разбор srcset в пары (размер, url). Множитель `2x` без размера в url считается как 200
+fn parse_srcset(srcset: &str) -> Vec<(u32, String)> {
+    // В url VK бывают запятые (crop=0,0,200,200), поэтому куски без начала url
+    // приклеиваем к предыдущему элементу
+    let mut items: Vec<String> = Vec::new();
+    for piece in srcset.split(',') {
+        let trimmed = piece.trim_start();
+        match items.last_mut() {
+            Some(last) if !(trimmed.starts_with("http") || trimmed.starts_with('/')) => {
+                last.push(',');
+                last.push_str(piece);
+            }
+            _ => items.push(trimmed.to_string()),
+        }
+    }
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let mut parts = item.split_whitespace();
+            let url = parts.next()?.to_string();
+            let declared = match parts.next() {
+                Some(d) if d.ends_with('w') => d.trim_end_matches('w').parse().ok()?,
+                Some(d) if d.ends_with('x') => {
+                    (d.trim_end_matches('x').parse::<f32>().ok()? * 100.0) as u32
+                }
+                _ => 0,
+            };
+            // Размер в самом url точнее дескриптора
+            let size = vk_image_size(&url).unwrap_or(0).max(declared);
+            Some((size, url))
+        })
+        .collect()
+}
+
+// Поля идентификатора приложения и иконки в данных страниц магазинов
+const STORE_APP_ID_KEYS: &[&str] = &["packageName", "appid", "appId", "package"];
+const STORE_ICON_KEYS: &[&str] = &["iconUrl", "icoUri"];
+// Параметры размера googleusercontent: =s64, =w240-h480, =w240-h480-rw
+static GOOGLE_SIZE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"=[swh]\d+[-\w]*$").unwrap());
+
+const GOOGLE_PLAY_ICON_SIZE: u32 = 512;
+
+/// Иконка приложения RuStore или AppGallery из записи самого приложения в данных страницы
+/// (`iconUrl`, `icoUri`) или `og:image`. На странице есть и похожие приложения со своими
+/// иконками, поэтому запись ищется по идентификатору `app_id` (имя пакета или C123456).
+/// Подходит и для JSON ответа API деталей приложения AppGallery
+pub fn store_page_icon(html: &str, app_id: &str) -> Option<String> {
+    let from_data = match serde_json::from_str::<serde_json::Value>(html) {
+        Ok(json) => store_app_icon(&json, app_id),
+        Err(_) => {
+            let document = Html::parse_document(html);
+            let scripts = Selector::parse("script").ok()?;
+            let icon = document
+                .select(&scripts)
+                .filter_map(|script| script_json(&script.text().collect::<String>()))
+                .find_map(|json| store_app_icon(&json, app_id));
+            icon.or_else(|| select_attr(&document, "meta[property='og:image']", "content"))
+        }
+    };
+    from_data.map(with_scheme)
+}
+
+/// JSON из скрипта страницы: `<script type="application/json">` целиком
+/// или объект в присваивании `window.__STATE__ = {...};`
+fn script_json(text: &str) -> Option<serde_json::Value> {
+    let text = text.trim();
+    serde_json::from_str(text).ok().or_else(|| {
+        let start = text.find('{')?;
+        let end = text.rfind('}')?;
+        serde_json::from_str(text.get(start..=end)?).ok()
+    })
+}
+
+/// Поиск объекта с идентификатором приложения и его иконки
+fn store_app_icon(value: &serde_json::Value, app_id: &str) -> Option<String> {
+    match value {
+        serde_json::Value::Array(items) => {
+            items.iter().find_map(|item| store_app_icon(item, app_id))
+        }
+        serde_json::Value::Object(map) => {
+            let is_app = STORE_APP_ID_KEYS.iter().any(|key| {
+                map.get(*key)
+                    .and_then(|id| id.as_str())
+                    .is_some_and(|id| id.eq_ignore_ascii_case(app_id))
+            });
+            let icon = STORE_ICON_KEYS
+                .iter()
+                .find_map(|key| map.get(*key).and_then(|icon| icon.as_str()))
+                .filter(|icon| is_app && !icon.is_empty());
+            match icon {
+                Some(icon) => Some(icon.to_string()),
+                None => map.values().find_map(|item| store_app_icon(item, app_id)),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Иконка приложения Google Play с размером, переписанным на `=s512`
+pub fn google_play_icon(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+
+    select_attr(&document, "img[itemprop='image']", "src")
+        .or_else(|| select_attr(&document, "meta[property='og:image']", "content"))
+        .map(|url| google_image_size(&url, GOOGLE_PLAY_ICON_SIZE))
+}
+
+/// Переписывает размер картинки googleusercontent (`=s64`, `=w240-h480`) на `=s<size>`
+pub fn google_image_size(url: &str, size: u32) -> String {
+    if !url.contains("googleusercontent.com") {
+        return url.to_string();
+    }
+    let base = GOOGLE_SIZE_REGEX.replace(url, "");
+    format!("{base}=s{size}")
+}
+
+// Шаблон иконки App Store: "artwork":{"url":"https://is1-ssl.mzstatic.com/.../{w}x{h}{c}.{f}","width":1024
+static APPSTORE_ARTWORK_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#""artwork"\s*:\s*\{[^{}]*?"url"\s*:\s*"(https?:[^"]+\{w\}x\{h\}[^"]*)"(?:[^{}]*?"width"\s*:\s*(\d+))?"#,
+    )
+    .unwrap()
+});
+// Готовая картинка iTunes lookup API: .../512x512bb.jpg
+static APPSTORE_SIZED_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"/\d+x\d+[a-z]*\.(png|jpg|jpeg|webp)$").unwrap());
+// Идентификатор приложения в ссылке apps.apple.com/ru/app/name/id123456
+static APPSTORE_ID_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"/id(\d+)").unwrap());
+
+const APPSTORE_ICON_SIZE: u32 = 1024;
+
+/// Идентификатор приложения из ссылки App Store
+pub fn appstore_app_id(url: &str) -> Option<String> {
+    APPSTORE_ID_REGEX
+        .captures(url)
+        .map(|caps| caps[1].to_string())
+}
+
+/// Иконка App Store максимального размера в PNG по шаблону artwork из данных страницы
+pub fn appstore_icon(html: &str) -> Option<String> {
+    let html = html.replace("\\/", "/").replace("\\u002F", "/");
+    let caps = APPSTORE_ARTWORK_REGEX.captures(&html)?;
+    let size = caps
+        .get(2)
+        .and_then(|w| w.as_str().parse().ok())
+        .unwrap_or(APPSTORE_ICON_SIZE);
+
+    Some(
+        caps[1]
+            .replace("{w}", &size.to_string())
+            .replace("{h}", &size.to_string())
+            .replace("{c}", "bb")
+            .replace("{f}", "png"),
+    )
+}
+
+/// Иконка из ответа iTunes lookup API (`artworkUrl512`) с размером, переписанным на максимальный
+pub fn appstore_lookup_icon(json: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(json).ok()?;
+    let url = value["results"][0]["artworkUrl512"].as_str()?;
+    Some(
+        APPSTORE_SIZED_REGEX
+            .replace(
+                url,
+                format!("/{APPSTORE_ICON_SIZE}x{APPSTORE_ICON_SIZE}bb.png").as_str(),
+            )
+            .into_owned(),
+    )
+}
+
+// Суффикс размера картинок avatars.mds.yandex.net: .../get-altay/123/abc/S
+static YANDEX_AVATAR_SIZE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(https?://avatars\.mds\.yandex\.net/get-[\w-]+/\d+/[\w-]+)/[\w-]+$").unwrap()
+});
+
+pub const YANDEX_MAX_SIZE: &str = "XXXL";
+
+/// Логотип организации со страницы Яндекс Карт в максимальном размере
+pub fn yandex_org_logo(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+
+    // <img class="img-with-alt" alt="Логотип" src="https://avatars.mds.yandex.net/get-altay/.../S">
+    let by_alt = Selector::parse("img[alt]").ok().and_then(|selector| {
+        document
+            .select(&selector)
+            .filter(|img| {
+                img.value()
+                    .attr("alt")
+                    .is_some_and(|alt| alt.trim().to_lowercase() == "логотип")
+            })
+            .find_map(|img| img.value().attr("src").map(String::from))
+    });
+
+    by_alt
+        .or_else(|| select_attr(&document, "[class*='logo'] img", "src"))
+        .map(with_scheme)
+        .filter(|url| url.contains("avatars.mds.yandex.net"))
+        .map(|url| yandex_avatar_size(&url, YANDEX_MAX_SIZE))
+}
+
+/// Переписывает суффикс размера картинки avatars.mds.yandex.net (`/S`, `/M` ...) на `size`
+pub fn yandex_avatar_size(url: &str, size: &str) -> String {
+    YANDEX_AVATAR_SIZE_REGEX
+        .replace(url, format!("${{1}}/{size}").as_str())
+        .into_owned()
+}
+
+// Логотип работодателя в исходном размере: https://img.hhcdn.ru/employer-logo-original/123.png
+const HH_ORIGINAL_LOGO: &str = "img.hhcdn.ru/employer-logo-original/";
+// Ключи идентификатора работодателя в данных страницы hh.ru
+const HH_EMPLOYER_ID_KEYS: &[&str] = &["id", "employerId"];
+// Данные страницы hh.ru: `<template id="HH-Lux-InitialState">{...}</template>`
+static HH_INITIAL_STATE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?s)<template[^>]*id=["']HH-Lux-InitialState["'][^>]*>(.*?)</template>"#).unwrap()
+});
+
+/// Ссылка на страницу работодателя со страницы вакансии hh.ru
+pub fn hh_employer_link(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    select_attr(&document, "a[data-qa='vacancy-company-name']", "href")
+        .or_else(|| select_attr(&document, "a[href*='/employer/']", "href"))
+}
+
+/// Идентификатор работодателя из ссылки `hh.ru/employer/<id>`
+pub fn hh_employer_id(url: &Url) -> Option<String> {
+    let mut segments = url.path_segments()?;
+    segments.find(|segment| *segment == "employer")?;
+    segments
+        .next()
+        .filter(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+        .map(String::from)
+}
+
+/// Логотип работодателя hh.ru: ссылка на исходный размер из записи работодателя
+/// `employer_id` в данных страницы, элемент логотипа или `og:image`.
+/// В данных есть и вакансии других работодателей с их логотипами, поэтому
+/// без идентификатора они не используются
+pub fn hh_employer_logo(html: &str, employer_id: Option<&str>) -> Option<String> {
+    let from_data = employer_id.and_then(|employer_id| {
+        HH_INITIAL_STATE_REGEX
+            .captures_iter(html)
+            .filter_map(|captures| script_json(captures.get(1)?.as_str()))
+            .find_map(|json| hh_employer_object_logo(&json, employer_id))
+    });
+
+    from_data
+        .or_else(|| {
+            let document = Html::parse_document(html);
+            select_attr(&document, "img[data-qa='company-logo-image']", "src")
+                .or_else(|| select_attr(&document, "meta[property='og:image']", "content"))
+        })
+        .map(with_scheme)
+        // Без логотипа og:image указывает на общую картинку hh.ru
+        .filter(|url| url.contains("hhcdn.ru/employer-logo"))
+}
+
+/// Поиск записи работодателя по идентификатору и ссылки на исходный логотип в ней
+fn hh_employer_object_logo(value: &serde_json::Value, employer_id: &str) -> Option<String> {
+    match value {
+        serde_json::Value::Array(items) => items
+            .iter()
+            .find_map(|item| hh_employer_object_logo(item, employer_id)),
+        serde_json::Value::Object(map) => {
+            let is_employer = HH_EMPLOYER_ID_KEYS.iter().any(|key| {
+                map.get(*key).is_some_and(|id| match id {
+                    serde_json::Value::String(id) => id == employer_id,
+                    serde_json::Value::Number(id) => id.to_string() == employer_id,
+                    _ => false,
+                })
+            });
+            is_employer
+                .then(|| hh_original_logo(value))
+                .flatten()
+                .or_else(|| {
+                    map.values()
+                        .find_map(|item| hh_employer_object_logo(item, employer_id))
+                })
+        }
+        _ => None,
+    }
+}
+
+/// Первая ссылка на исходный логотип внутри записи
+fn hh_original_logo(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(url) if url.contains(HH_ORIGINAL_LOGO) => Some(url.clone()),
+        serde_json::Value::Array(items) => items.iter().find_map(hh_original_logo),
+        serde_json::Value::Object(map) => map.values().find_map(hh_original_logo),
+        _ => None,
+    }
+}
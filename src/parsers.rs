@@ -1,6 +1,17 @@
+use crate::cdn_urls::remove_query_params;
+use crate::config::Config;
+use crate::content_sniff::display_url;
 use crate::job_loaders::LogoJob;
+use crate::page_extractors::{
+    appstore_app_id, appstore_icon, appstore_lookup_icon, expand_short_link, fetch_bytes,
+    fetch_page, google_play_icon, hh_employer_id, hh_employer_link, hh_employer_logo,
+    is_short_link, join_http_url, store_page_icon, telegram_avatar, vk_avatar, yandex_org_logo,
+};
+use crate::site_rules::{site_rules, SiteRules};
+use crate::well_known::probe_well_known_icons;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use reqwest::Client;
 use scraper::{Html, Selector};
 use serde::Deserialize;
 use std::error::Error;
@@ -9,18 +20,148 @@ use url::Url;
 use std::collections::hash_map::DefaultHasher;
 use std::fs;
 use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
 
-#[derive(Debug)]
+// Кандидаты ниже этого приоритета — случайные картинки страницы, а не объявленный логотип.
+// Такие векторные кандидаты не предпочитаем
+const MIN_DECLARED_PRIORITY: u8 = 3;
+// Приоритет иконок, найденных по известным путям сайта
+const WELL_KNOWN_PRIORITY: u8 = 5;
+const SVG_MIME_TYPE: &str = "image/svg+xml";
+// Одноцветный силуэт для закреплённых вкладок Safari, а не полноцветный логотип
+const MASK_ICON_TYPE: &str = "mask-icon";
+// Встроенный в страницу SVG: у кандидата нет своей ссылки, файл пишется, только если он выбран
+const INLINE_SVG_TYPE: &str = "inline-svg";
+// Растровые форматы вложений, которые умеет читать обработка
+const RASTER_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp", "ico"];
+
+// Оценка кандидата: приоритет источника, заявленный размер, формат и пропорции.
+// Размер, формат и пропорции вместе меньше шага приоритета и решают только внутри источника
+const PRIORITY_WEIGHT: i32 = 100;
+// Размеры больше этого не дают преимущества
+const MAX_SCORED_SIZE: u32 = 1024;
+// Пикселей меньшей стороны на единицу оценки: 1024 дают 64
+const SIZE_SCORE_STEP: u32 = 16;
+const ASPECT_PENALTY_WEIGHT: f64 = 40.0;
+const MAX_ASPECT_PENALTY: i32 = 60;
+
+#[derive(Debug, Clone)]
 struct IconInfo {
     url: String,
     icon_type: String,
     sizes: Option<String>,
+    mime_type: Option<String>,
     priority: u8, // Приоритет: выше = лучше
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum IconFormat {
+    Svg,
+    Png,
+    Webp,
+    Jpeg,
+    Gif,
+    Ico,
+    Unknown,
+}
+
+impl IconFormat {
+    fn from_mime_type(mime_type: &str) -> Option<Self> {
+        let format = match mime_type.to_lowercase().as_str() {
+            SVG_MIME_TYPE => IconFormat::Svg,
+            "image/png" => IconFormat::Png,
+            "image/webp" => IconFormat::Webp,
+            "image/jpeg" | "image/jpg" => IconFormat::Jpeg,
+            "image/gif" => IconFormat::Gif,
+            "image/x-icon" | "image/vnd.microsoft.icon" => IconFormat::Ico,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    fn from_extension(extension: &str) -> Self {
+        match extension.to_lowercase().as_str() {
+            "svg" => IconFormat::Svg,
+            "png" => IconFormat::Png,
+            "webp" => IconFormat::Webp,
+            "jpg" | "jpeg" => IconFormat::Jpeg,
+            "gif" => IconFormat::Gif,
+            "ico" => IconFormat::Ico,
+            _ => IconFormat::Unknown,
+        }
+    }
+
+    /// Предпочтение формата: SVG > PNG > WebP > JPEG > GIF > ICO
+    fn score(self) -> i32 {
+        match self {
+            IconFormat::Svg => 30,
+            IconFormat::Png => 20,
+            IconFormat::Webp => 15,
+            IconFormat::Jpeg => 10,
+            IconFormat::Unknown => 8,
+            IconFormat::Gif => 5,
+            IconFormat::Ico => 0,
+        }
+    }
+}
+
+impl IconInfo {
+    fn is_vector(&self) -> bool {
+        self.format() == IconFormat::Svg
+    }
+
+    /// Формат по MIME типу, а если он не указан или неизвестен — по расширению в ссылке
+    fn format(&self) -> IconFormat {
+        self.mime_type
+            .as_deref()
+            .and_then(IconFormat::from_mime_type)
+            .unwrap_or_else(|| {
+                Url::parse(&self.url)
+                    .ok()
+                    .and_then(|url| {
+                        url.path()
+                            .rsplit_once('.')
+                            .map(|(_, extension)| IconFormat::from_extension(extension))
+                    })
+                    .unwrap_or(IconFormat::Unknown)
+            })
+    }
+
+    fn score(&self) -> i32 {
+        let dimensions = self.sizes.as_deref().and_then(parse_size);
+
+        // Размер оценивается по меньшей стороне
+        let size_score = dimensions
+            .map(|(w, h)| (w.min(h).min(MAX_SCORED_SIZE) / SIZE_SCORE_STEP) as i32)
+            .unwrap_or(0);
+
+        // Штраф за отклонение от квадрата: логотип вписывается в квадратный холст
+        let aspect_penalty = dimensions
+            .filter(|&(w, h)| w.min(h) > 0)
+            .map(|(w, h)| {
+                let ratio = w.max(h) as f64 / w.min(h) as f64;
+                (((ratio - 1.0) * ASPECT_PENALTY_WEIGHT) as i32).min(MAX_ASPECT_PENALTY)
+            })
+            .unwrap_or(0);
+
+        let bonus =
+            (size_score + self.format().score() - aspect_penalty).clamp(0, PRIORITY_WEIGHT - 1);
+        self.priority as i32 * PRIORITY_WEIGHT + bonus
+    }
+
+    /// Объявленный сайтом векторный логотип предпочитается растровым независимо от оценки.
+    /// Силуэт mask-icon и случайные SVG картинки страницы не в счёт
+    fn is_preferred_vector(&self) -> bool {
+        self.is_vector()
+            && self.priority >= MIN_DECLARED_PRIORITY
+            && self.icon_type != MASK_ICON_TYPE
+    }
+}
+
 #[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub enum UrlType {
-    JSONJob(String),
+    /// Сайт с правилом извлечения из файла правил
+    Rule(String),
     Telegram(String),
     YandexMap(String),
     AppGallery(String),
@@ -31,7 +172,6 @@ pub enum UrlType {
     VK(String),
     HH(String),
     Other(String),
-    None,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -55,18 +195,35 @@ pub struct DataItem {
 }
 impl DataItem {
     // Парсим json задания
-    pub fn get_job(&self) -> Result<Option<LogoJob>, Box<dyn Error>> {
-        // Сначала пытаемся извлечь данные из attachments
-        if let UrlType::JSONJob(url) = UrlType::from_attachments(&self.attachments) {
-            return Ok(Some(LogoJob::new(self.id, url)));
+    pub fn get_job(&self) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        // Сначала пытаемся извлечь данные из attachments.
+        // Остальные подходящие вложения — запасные, если лучшее не скачается
+        let mut attachments = ranked_attachments(&self.attachments).into_iter();
+        if let Some(best) = attachments.next() {
+            let fallback_urls = attachments.map(|a| a.url.clone()).collect();
+            return Ok(Some(
+                LogoJob::new(self.id, best.url.clone()).with_fallback_urls(fallback_urls),
+            ));
         }
 
         Ok(None)
-        // // или из заметок
-        // match UrlType::from_note(&self.note) {
-        //     Some(url_type) => url_type.process(self.id).await,
-        //     None => Ok(None),
-        // }
+    }
+
+    /// Ссылка на логотип из вложений, а если их нет — извлечённая со страницы из заметки
+    pub async fn resolve_job(
+        &self,
+        client: &Client,
+        config: &Config,
+    ) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        if let Some(job) = self.get_job()? {
+            return Ok(Some(job));
+        }
+
+        // или из заметок
+        match UrlType::from_note(&self.note, client, config).await {
+            Some(url_type) => url_type.process(self.id, client, config).await,
+            None => Ok(None),
+        }
     }
 }
 
@@ -75,75 +232,550 @@ impl DataItem {
 pub struct Attachment {
     pub url: String,
     pub id: u32,
+    #[serde(default, alias = "fileName")]
+    pub name: Option<String>,
+    #[serde(default, alias = "mimeType")]
+    pub content_type: Option<String>,
+}
+
+/// Вид вложения для выбора логотипа, в порядке предпочтения
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum AttachmentKind {
+    Vector,
+    Raster,
+    /// Нет ни типа, ни расширения: может оказаться картинкой
+    Unknown,
+    /// Документы, архивы, видео
+    NotImage,
+}
+
+impl Attachment {
+    fn kind(&self) -> AttachmentKind {
+        if let Some(content_type) = &self.content_type {
+            let content_type = content_type.to_lowercase();
+            if content_type.starts_with(SVG_MIME_TYPE) {
+                return AttachmentKind::Vector;
+            }
+            if content_type.starts_with("image/") {
+                return AttachmentKind::Raster;
+            }
+            if !content_type.starts_with("application/octet-stream") {
+                return AttachmentKind::NotImage;
+            }
+        }
+
+        // Расширение из имени файла, а если его нет — из ссылки
+        let path = Url::parse(&self.url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_default();
+        let extension = [self.name.as_deref().unwrap_or_default(), path.as_str()]
+            .into_iter()
+            .find_map(|name| {
+                let file_name = name.rsplit('/').next()?;
+                file_name
+                    .rsplit_once('.')
+                    .map(|(_, extension)| extension.to_lowercase())
+            });
+
+        match extension.as_deref() {
+            None => AttachmentKind::Unknown,
+            Some("svg") => AttachmentKind::Vector,
+            Some(extension) if RASTER_EXTENSIONS.contains(&extension) => AttachmentKind::Raster,
+            Some(_) => AttachmentKind::NotImage,
+        }
+    }
+}
+
+/// Вложения, подходящие для логотипа, от лучшего: векторные, растровые, без типа.
+/// Среди равных впереди более новые (с большим id)
+fn ranked_attachments(attachments: &[Attachment]) -> Vec<&Attachment> {
+    let mut ranked: Vec<(AttachmentKind, &Attachment)> = attachments
+        .iter()
+        .map(|attachment| (attachment.kind(), attachment))
+        .filter(|(kind, attachment)| {
+            if *kind == AttachmentKind::NotImage {
+                log::info!("Вложение {} пропущено: не картинка", attachment.url);
+            }
+            *kind != AttachmentKind::NotImage
+        })
+        .collect();
+    ranked.sort_by_key(|(kind, attachment)| (*kind, std::cmp::Reverse(attachment.id)));
+    ranked
+        .into_iter()
+        .map(|(_, attachment)| attachment)
+        .collect()
 }
 
 impl UrlType {
-    // Получить Url логотипа задания для скачки
-    pub fn from_note(note: &str) -> Option<UrlType> {
-        note.trim()
-            .lines()
-            .map(str::trim)
-            .filter(|s| !s.is_empty())
-            .filter(|s| s.starts_with("https://") || s.starts_with("http://"))
-            .map(UrlType::from_str)
-            .min()
+    // Получить Url логотипа задания для скачки.
+    // Короткие ссылки раскрываются до определения типа, если это разрешено в конфигурации
+    pub async fn from_note(note: &str, client: &Client, config: &Config) -> Option<UrlType> {
+        let rules = site_rules(config);
+        let mut url_types = Vec::new();
+
+        for url in note_urls(note) {
+            let url = if config.expand_links() && is_short_link(url) {
+                expand_short_link(client, url).await
+            } else {
+                url.to_string()
+            };
+            url_types.push(UrlType::from_str(&url, &rules));
+        }
+
+        url_types.into_iter().min()
     }
 
-    pub fn from_attachments(attachments: &[Attachment]) -> UrlType {
-        attachments
+    pub async fn process(
+        &self,
+        id: u32,
+        client: &Client,
+        config: &Config,
+    ) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        match self {
+            UrlType::Telegram(url) => Self::process_telegram_page(id, &url, client).await,
+            UrlType::VK(url) => Self::process_vk_page(id, &url, client).await,
+            UrlType::RuStore(url) => Self::process_rustore_page(id, &url, client).await,
+            UrlType::AppGallery(url) => Self::process_appgallery_page(id, &url, client).await,
+            UrlType::GooglePlay(url) => Self::process_googleplay_page(id, &url, client).await,
+            UrlType::AppleApp(url) => Self::process_appstore_page(id, &url, client).await,
+            UrlType::YandexMap(url) => Self::process_yandex_page(id, url, client).await,
+            UrlType::ImageUrl(url) => Self::process_image_page(id, url).await,
+            UrlType::HH(url) => Self::process_hh_page(id, url, client).await,
+            UrlType::Rule(url) | UrlType::Other(url) => {
+                Self::process_web_page(id, &url, client, config).await
+            }
+        }
+    }
+
+    async fn process_telegram_page(
+        id: u32,
+        url: &str,
+        client: &Client,
+    ) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        let parsed = Url::parse(url)?;
+        let mut segments = parsed
+            .path_segments()
             .into_iter()
-            .max_by_key(|x| x.id)
-            .map(|attachment| UrlType::JSONJob(attachment.url.clone()))
-            .unwrap_or(UrlType::None)
+            .flatten()
+            .filter(|s| !s.is_empty());
+
+        // t.me/<канал>, t.me/<канал>/<пост> и лента t.me/s/<канал>
+        let name = match segments.next() {
+            Some("s") => segments.next(),
+            other => other,
+        }
+        .ok_or_else(|| format!("Telegram {url}: в ссылке нет имени канала"))?;
+
+        if name.starts_with('+') || name == "joinchat" {
+            return Err(format!("Telegram {url}: нет аватара, ссылка на приватный канал").into());
+        }
+
+        let html = fetch_page(client, &format!("https://t.me/{name}")).await?;
+        let avatar = telegram_avatar(&html).ok_or_else(|| {
+            format!("Telegram {url}: нет аватара, канал приватный или аватар не задан")
+        })?;
+
+        println!("Аватар Telegram {url} -> {avatar}");
+        Ok(Some(LogoJob::new(id, avatar)))
     }
 
-    pub async fn process(&self, id: u32) -> Result<Option<LogoJob>, Box<dyn Error>> {
-        match self {
-            UrlType::Telegram(url) => Self::process_telegram_page(id, &url).await,
-            UrlType::VK(url) => Self::process_vk_page(id, &url).await,
-            UrlType::RuStore(url) => Self::process_rustore_page(id, &url).await,
-            UrlType::AppleApp(url) => Self::process_appstore_page(id, &url).await,
-            UrlType::YandexMap(url) => {
-                //<img class="img-with-alt" width="100%" height="100%" alt="Логотип" src="https://avatars.mds.yandex.net/get-altay/14031726/2a000001953765424f202da2cf59a2007866/S" style="object-fit: contain;">
-                Self::process_yandex_page(id, url).await
+    async fn process_vk_page(
+        id: u32,
+        url: &str,
+        client: &Client,
+    ) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        let html = fetch_page(client, url).await?;
+        let avatar = vk_avatar(&html)
+            .ok_or_else(|| format!("VK {url}: нет аватара сообщества или страница закрыта"))?;
+
+        println!("Аватар VK {url} -> {avatar}");
+        Ok(Some(LogoJob::new(id, avatar)))
+    }
+
+    async fn process_rustore_page(
+        id: u32,
+        url: &str,
+        client: &Client,
+    ) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        // rustore.ru/catalog/app/<пакет>, apps.rustore.ru/app/<пакет>
+        let package = Url::parse(url)?
+            .path_segments()
+            .and_then(|segments| segments.filter(|s| !s.is_empty()).last().map(String::from))
+            .ok_or_else(|| format!("RuStore {url}: в ссылке нет имени пакета"))?;
+
+        let html = fetch_page(client, url).await?;
+        let icon = store_page_icon(&html, &package)
+            .ok_or_else(|| format!("RuStore {url}: иконка приложения не найдена"))?;
+
+        println!("Иконка RuStore {url} -> {icon}");
+        Ok(Some(LogoJob::new_app_icon(id, icon)))
+    }
+
+    async fn process_appgallery_page(
+        id: u32,
+        url: &str,
+        client: &Client,
+    ) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        // Страница AppGallery собирается скриптами, поэтому при пустом HTML
+        // берём иконку из API деталей приложения по идентификатору C<число>
+        const DETAIL_API: &str =
+            "https://web-drru.hispace.dbankcloud.com/uowap/index?method=internal.getTabDetail&uri=app%7C";
+
+        // appgallery.huawei.com/app/C101184875, старые ссылки: /#/app/C101184875
+        let parsed = Url::parse(url)?;
+        let app_id = parsed
+            .path()
+            .split('/')
+            .chain(parsed.fragment().unwrap_or_default().split('/'))
+            .find(|s| {
+                s.len() > 1 && s.starts_with('C') && s[1..].bytes().all(|b| b.is_ascii_digit())
+            })
+            .ok_or_else(|| format!("AppGallery {url}: в ссылке нет id приложения"))?
+            .to_string();
+
+        let mut icon = store_page_icon(&fetch_page(client, url).await?, &app_id);
+        if icon.is_none() {
+            let detail = fetch_page(client, &format!("{DETAIL_API}{app_id}")).await?;
+            icon = store_page_icon(&detail, &app_id);
+        }
+        let icon = icon.ok_or_else(|| format!("AppGallery {url}: иконка приложения не найдена"))?;
+
+        println!("Иконка AppGallery {url} -> {icon}");
+        Ok(Some(LogoJob::new_app_icon(id, icon)))
+    }
+
+    async fn process_googleplay_page(
+        id: u32,
+        url: &str,
+        client: &Client,
+    ) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        let html = fetch_page(client, url).await?;
+        let icon = google_play_icon(&html)
+            .ok_or_else(|| format!("Google Play {url}: иконка приложения не найдена"))?;
+
+        println!("Иконка Google Play {url} -> {icon}");
+        Ok(Some(LogoJob::new_app_icon(id, icon)))
+    }
+
+    async fn process_appstore_page(
+        id: u32,
+        url: &str,
+        client: &Client,
+    ) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        const LOOKUP_API: &str = "https://itunes.apple.com/lookup?id=";
+
+        let app_id = appstore_app_id(url)
+            .ok_or_else(|| format!("AppStore {url}: в ссылке нет id приложения"))?;
+
+        // Шаблон artwork со страницы, а если его нет — картинка из iTunes lookup API
+        let icon = match appstore_icon(&fetch_page(client, url).await?) {
+            Some(icon) => Some(icon),
+            None => {
+                appstore_lookup_icon(&fetch_page(client, &format!("{LOOKUP_API}{app_id}")).await?)
             }
-            UrlType::ImageUrl(url) => Self::process_image_page(id, url).await,
-            UrlType::HH(url) => {
-                // Специальная обработка для HH
-                Self::process_hh_page(id, url).await
+        }
+        .ok_or_else(|| format!("AppStore {url}: иконка приложения {app_id} не найдена"))?;
+
+        println!("Иконка AppStore {url} -> {icon}");
+        Ok(Some(LogoJob::new_app_icon(id, icon)))
+    }
+
+    async fn process_web_page(
+        id: u32,
+        url: &str,
+        client: &Client,
+        config: &Config,
+    ) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        let html = fetch_page(client, url).await?;
+        let base_url = Url::parse(url)?;
+        let page = PageIcons::collect(&html, &base_url, &site_rules(config));
+
+        // Правило сайта задаёт логотип явно: оценка и векторные источники его не перебивают
+        if let Some(icon) = select_best_icon(&page.rule_icons) {
+            return Ok(Some(LogoJob::new(id, icon.url.clone())));
+        }
+        let mut icons = page.icons;
+
+        // Иконки из манифеста веб-приложения
+        if let Some(manifest_url) = page.manifest_url {
+            match fetch_page(client, manifest_url.as_str()).await {
+                Ok(manifest) => icons.extend(manifest_icons(&manifest, &manifest_url)),
+                Err(e) => log::warn!("Не удалось загрузить манифест {manifest_url}: {e}"),
+            }
+        }
+
+        // Встроенный в страницу SVG логотип
+        let mut inline_svg_url = base_url.clone();
+        inline_svg_url.set_fragment(Some(INLINE_SVG_TYPE));
+        if page.inline_svg.is_some() {
+            icons.push(IconInfo {
+                url: inline_svg_url.to_string(),
+                icon_type: INLINE_SVG_TYPE.to_string(),
+                sizes: None,
+                mime_type: Some(SVG_MIME_TYPE.to_string()),
+                priority: 4,
+            });
+        }
+
+        // Сайт не объявляет логотип — проверяем известные пути иконок
+        if !icons
+            .iter()
+            .any(|icon| icon.priority >= MIN_DECLARED_PRIORITY)
+        {
+            let well_known =
+                probe_well_known_icons(client, &base_url, &config.well_known_paths()).await;
+            icons.extend(well_known.into_iter().map(|icon| IconInfo {
+                url: icon.url,
+                icon_type: "well-known".to_string(),
+                sizes: icon.dimensions.map(|(w, h)| format!("{w}x{h}")),
+                mime_type: Some(icon.mime_type),
+                priority: WELL_KNOWN_PRIORITY,
+            }));
+        }
+
+        // Проверка лучших кандидатов по реальным размерам картинок
+        let icons = match config.probe_candidates() {
+            0 => icons,
+            probe_count => {
+                let top = rank_icons(&icons).into_iter().take(probe_count).collect();
+                let probed = probe_icons(client, top).await;
+                if probed.is_empty() {
+                    icons
+                } else {
+                    probed
+                }
+            }
+        };
+
+        // Выбираем лучший логотип по оценке, векторные источники в приоритете
+        let best = select_best_icon(&icons).ok_or_else(|| {
+            Box::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Логотип не найден",
+            )) as Box<dyn Error + Send + Sync>
+        })?;
+
+        match &page.inline_svg {
+            Some(svg)
+                if best.icon_type == INLINE_SVG_TYPE && best.url == inline_svg_url.as_str() =>
+            {
+                let path = save_inline_svg(config, id, svg).await?;
+                println!("Встроенный SVG {url} -> {}", path.display());
+                Ok(Some(LogoJob::new_inline_svg(id, url.to_string(), path)))
             }
-            UrlType::Other(url) => Self::process_web_page(id, &url).await,
-            _ => Ok(None),
+            _ => Ok(Some(LogoJob::new(id, best.url.clone()))),
         }
     }
 
-    async fn process_telegram_page(id: u32, url: &str) -> Result<Option<LogoJob>, Box<dyn Error>> {
-        println!("Не обрабатываем Телегу: {}", url);
+    async fn process_image_page(
+        id: u32,
+        url: &str,
+    ) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        println!("Обработка ссылки на картинку {}", display_url(url));
         Ok(Some(LogoJob::new(id, url.to_string())))
     }
 
-    async fn process_vk_page(id: u32, url: &str) -> Result<Option<LogoJob>, Box<dyn Error>> {
-        // https://smm-e.ru/services/vk/groups/download-cover/
-        println!("Не обрабатываем VK: {}", url);
-        Ok(Some(LogoJob::new(id, url.to_string())))
+    async fn process_yandex_page(
+        id: u32,
+        url: &str,
+        client: &Client,
+    ) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        let html = fetch_page(client, url).await?;
+        let logo = yandex_org_logo(&html)
+            .ok_or_else(|| format!("Яндекс Карты {url}: у организации нет логотипа"))?;
+
+        println!("Логотип Яндекс Карт {url} -> {logo}");
+        Ok(Some(LogoJob::new(id, logo)))
     }
 
-    async fn process_rustore_page(id: u32, url: &str) -> Result<Option<LogoJob>, Box<dyn Error>> {
-        println!("Не обрабатываем RuStore: {}", url);
-        Ok(Some(LogoJob::new(id, url.to_string())))
+    async fn process_hh_page(
+        id: u32,
+        url: &str,
+        client: &Client,
+    ) -> Result<Option<LogoJob>, Box<dyn Error + Send + Sync>> {
+        let mut page_url = Url::parse(url)?;
+        let mut html = fetch_page(client, url).await?;
+
+        // Со страницы вакансии переходим на страницу работодателя
+        if page_url.path().starts_with("/vacancy/") {
+            let employer = hh_employer_link(&html)
+                .ok_or_else(|| format!("hh.ru {url}: у вакансии нет ссылки на работодателя"))?;
+            page_url = page_url.join(&employer)?;
+            html = fetch_page(client, page_url.as_str()).await?;
+        }
+
+        let logo = hh_employer_logo(&html, hh_employer_id(&page_url).as_deref())
+            .ok_or_else(|| format!("hh.ru {page_url}: у работодателя нет логотипа"))?;
+
+        println!("Логотип hh.ru {url} -> {logo}");
+        Ok(Some(LogoJob::new(id, logo)))
     }
 
-    async fn process_appstore_page(id: u32, url: &str) -> Result<Option<LogoJob>, Box<dyn Error>> {
-        println!("Не обрабатываем AppStore: {}", url);
-        Ok(Some(LogoJob::new(id, url.to_string())))
+    pub fn from_str(url: &str, rules: &SiteRules) -> Self {
+        // Регулярное выражение создается один раз
+        static IMAGE_REGEX: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"(?i)\.(jpg|jpeg|png|gif|webp|svg|ico)(\?|$|/)").unwrap());
+
+        // Известные сайты: домен после нормализации и начало пути
+        const HOSTS: &[(&str, &str, fn(String) -> UrlType)] = &[
+            ("vk.com", "/", UrlType::VK),
+            ("vk.ru", "/", UrlType::VK),
+            ("t.me", "/", UrlType::Telegram),
+            ("rustore.ru", "/", UrlType::RuStore),
+            ("apps.rustore.ru", "/", UrlType::RuStore),
+            ("apps.apple.com", "/", UrlType::AppleApp),
+            ("itunes.apple.com", "/", UrlType::AppleApp),
+            ("appgallery.huawei.com", "/", UrlType::AppGallery),
+            ("play.google.com", "/store/apps/", UrlType::GooglePlay),
+            ("yandex.ru", "/maps/", UrlType::YandexMap),
+            ("yandex.com", "/maps/", UrlType::YandexMap),
+        ];
+
+        // Если есть картинка, в том числе встроенная в ссылку `data:image/...`
+        if url.starts_with("data:image/") || IMAGE_REGEX.is_match(&url.to_lowercase()) {
+            return UrlType::ImageUrl(url.to_string());
+        }
+
+        // Правила из файла важнее встроенных обработчиков
+        if rules.matches(url) {
+            return UrlType::Rule(url.to_string());
+        }
+
+        let Some(normalized) = normalize_url(url) else {
+            return UrlType::Other(url.to_string());
+        };
+        let host = normalized.host_str().unwrap_or_default();
+
+        // Типизируем по домену
+        for (pattern_host, path, constructor) in HOSTS {
+            if host == *pattern_host && normalized.path().starts_with(path) {
+                return constructor(normalized.to_string());
+            }
+        }
+
+        // HH: `https://spb.hh.ru/...` и другие поддомены
+        if host == "hh.ru" || host.ends_with(".hh.ru") {
+            return UrlType::HH(normalized.to_string());
+        }
+
+        UrlType::Other(strip_tracking_params(url))
     }
+}
 
-    async fn process_web_page(id: u32, url: &str) -> Result<Option<LogoJob>, Box<dyn Error>> {
-        let html = reqwest::get(url).await?.text().await?;
-        let document = Html::parse_document(&html);
-        let base_url = Url::parse(url)?;
+/// Строки заметки, похожие на ссылки
+fn note_urls(note: &str) -> impl Iterator<Item = &str> {
+    note.trim()
+        .lines()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter(|s| {
+            s.starts_with("https://") || s.starts_with("http://") || s.starts_with("data:image/")
+        })
+}
+
+/// Приведение ссылки к виду для определения типа сайта: https, без `www.` и `m.`,
+/// `telegram.me` как `t.me`, без параметров отслеживания
+fn normalize_url(url: &str) -> Option<Url> {
+    let mut parsed = Url::parse(url).ok()?;
+    if parsed.scheme() == "http" {
+        parsed.set_scheme("https").ok()?;
+    }
+
+    let host = parsed.host_str()?.to_lowercase();
+    let host = host
+        .strip_prefix("www.")
+        .or_else(|| host.strip_prefix("m."))
+        .unwrap_or(&host);
+    let host = match host {
+        "telegram.me" => "t.me",
+        host => host,
+    }
+    .to_string();
+    parsed.set_host(Some(&host)).ok()?;
+
+    remove_tracking_params(&mut parsed);
+    Some(parsed)
+}
+
+/// Ссылка без параметров отслеживания (`utm_*`, `fbclid`, `yclid` ...)
+fn strip_tracking_params(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) => {
+            remove_tracking_params(&mut parsed);
+            parsed.to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+fn remove_tracking_params(url: &mut Url) {
+    const TRACKING_PARAMS: &[&str] = &[
+        "fbclid",
+        "gclid",
+        "yclid",
+        "ysclid",
+        "_openstat",
+        "igshid",
+        "mc_cid",
+        "mc_eid",
+    ];
+
+    remove_query_params(url, |key| {
+        key.starts_with("utm_") || TRACKING_PARAMS.contains(&key)
+    });
+}
+
+/// Кандидаты в логотипы, собранные с HTML страницы сайта
+struct PageIcons {
+    /// Ссылки по правилам сайта из файла правил
+    rule_icons: Vec<IconInfo>,
+    icons: Vec<IconInfo>,
+    manifest_url: Option<Url>,
+    inline_svg: Option<String>,
+}
+
+impl PageIcons {
+    /// Разбор HTML выполняется синхронно, чтобы документ не жил между await
+    fn collect(html: &str, base_url: &Url, rules: &SiteRules) -> PageIcons {
+        let document = Html::parse_document(html);
+
+        // Логотип по правилу сайта из файла правил
+        let rule_icons: Vec<IconInfo> = rules
+            .extract(&document, base_url)
+            .into_iter()
+            .map(|rule| IconInfo {
+                url: rule.url,
+                icon_type: "site-rule".to_string(),
+                sizes: None,
+                mime_type: None,
+                priority: rule.priority,
+            })
+            .collect();
         let mut icons = Vec::new();
 
+        // Наивысший приоритет: логотип организации из schema.org JSON-LD
+        if let Ok(selector) = Selector::parse("script[type='application/ld+json']") {
+            for script in document.select(&selector) {
+                let text = script.text().collect::<String>();
+                let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) else {
+                    continue;
+                };
+                let mut logos = Vec::new();
+                json_ld_logos(&json, &mut logos);
+                for logo in logos {
+                    if let Some(logo_url) = join_http_url(base_url, &logo) {
+                        icons.push(IconInfo {
+                            url: logo_url.to_string(),
+                            icon_type: "json-ld".to_string(),
+                            sizes: None,
+                            mime_type: None,
+                            priority: 11,
+                        });
+                    }
+                }
+            }
+        }
+
         // Высокий приоритет: мета-теги и специальные иконки
         let meta_selectors = [
             (10, "meta[property='og:image']", "content", "og:image"),
@@ -154,20 +786,26 @@ impl UrlType {
                 "href",
                 "apple-touch-icon",
             ),
-            (7, "link[rel='mask-icon']", "href", "mask-icon"),
             (6, "link[rel='fluid-icon']", "href", "fluid-icon"),
             (5, "link[rel='icon']", "href", "icon"),
             (4, "link[rel='shortcut icon']", "href", "shortcut icon"),
+            // Силуэт уступает всем объявленным растровым иконкам
+            (3, "link[rel='mask-icon']", "href", MASK_ICON_TYPE),
         ];
 
         for (priority, selector_str, attr, icon_type) in &meta_selectors {
             if let Ok(selector) = Selector::parse(selector_str) {
                 for el in document.select(&selector) {
-                    if let Some(val) = el.value().attr(attr).and_then(|v| base_url.join(v).ok()) {
+                    if let Some(val) = el
+                        .value()
+                        .attr(attr)
+                        .and_then(|v| join_http_url(base_url, v))
+                    {
                         icons.push(IconInfo {
                             url: val.to_string(),
                             icon_type: icon_type.to_string(),
                             sizes: el.value().attr("sizes").map(String::from),
+                            mime_type: el.value().attr("type").map(String::from),
                             priority: *priority,
                         });
                     }
@@ -181,13 +819,16 @@ impl UrlType {
                 if let Some(alt) = img.value().attr("alt") {
                     let alt_lower = alt.to_lowercase();
                     if alt_lower.contains("logo") || alt_lower.contains("логотип") {
-                        if let Some(src) =
-                            img.value().attr("src").and_then(|s| base_url.join(s).ok())
+                        if let Some(src) = img
+                            .value()
+                            .attr("src")
+                            .and_then(|s| join_http_url(base_url, s))
                         {
                             icons.push(IconInfo {
                                 url: src.to_string(),
                                 icon_type: "logo-alt".to_string(),
                                 sizes: img.value().attr("sizes").map(String::from),
+                                mime_type: None,
                                 priority: 3,
                             });
                         }
@@ -200,11 +841,16 @@ impl UrlType {
         for selector_str in &["header img", "nav img", ".logo img", "[class*='logo'] img"] {
             if let Ok(selector) = Selector::parse(selector_str) {
                 for img in document.select(&selector) {
-                    if let Some(src) = img.value().attr("src").and_then(|s| base_url.join(s).ok()) {
+                    if let Some(src) = img
+                        .value()
+                        .attr("src")
+                        .and_then(|s| join_http_url(base_url, s))
+                    {
                         icons.push(IconInfo {
                             url: src.to_string(),
                             icon_type: "header-logo".to_string(),
                             sizes: img.value().attr("sizes").map(String::from),
+                            mime_type: None,
                             priority: 2,
                         });
                     }
@@ -215,7 +861,11 @@ impl UrlType {
         // Низкий приоритет: все остальные изображения (квадратные, небольшие)
         if let Ok(selector) = Selector::parse("img[src]") {
             for img in document.select(&selector) {
-                if let Some(src) = img.value().attr("src").and_then(|s| base_url.join(s).ok()) {
+                if let Some(src) = img
+                    .value()
+                    .attr("src")
+                    .and_then(|s| join_http_url(base_url, s))
+                {
                     let src_str = src.to_string();
                     // Пропускаем явно не логотипы
                     if !src_str.contains("banner")
@@ -226,6 +876,7 @@ impl UrlType {
                             url: src_str,
                             icon_type: "image".to_string(),
                             sizes: img.value().attr("sizes").map(String::from),
+                            mime_type: None,
                             priority: 1,
                         });
                     }
@@ -233,100 +884,180 @@ impl UrlType {
             }
         }
 
-        // Выбираем лучший логотип по приоритету и размеру
-        select_best_icon(&icons)
-            .map(|icon| LogoJob::new(id, icon.url.clone()))
-            .map(Some)
-            .ok_or_else(|| {
-                Box::new(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "Логотип не найден",
-                )) as Box<dyn Error>
+        let manifest_url = Selector::parse("link[rel='manifest']")
+            .ok()
+            .and_then(|selector| {
+                document
+                    .select(&selector)
+                    .find_map(|el| el.value().attr("href"))
             })
-    }
+            .and_then(|href| join_http_url(base_url, href));
 
-    async fn process_image_page(id: u32, url: &str) -> Result<Option<LogoJob>, Box<dyn Error>> {
-        println!("Обработка ссылки на картинку {url}");
-        Ok(Some(LogoJob::new(id, url.to_string())))
-    }
+        // Встроенный SVG в контейнере логотипа или в ссылке на главную в шапке
+        let inline_svg = [
+            "[class*='logo'] svg",
+            "[id*='logo'] svg",
+            "svg[class*='logo']",
+            "header a[href='/'] svg",
+        ]
+        .iter()
+        .filter_map(|selector| Selector::parse(selector).ok())
+        .find_map(|selector| {
+            document
+                .select(&selector)
+                .map(|svg| svg.html())
+                // Ссылки на спрайт (<use href="#...">) без своей графики вне страницы пусты
+                .find(|svg| {
+                    svg.contains("<path") || svg.contains("<polygon") || svg.contains("<circle")
+                })
+        })
+        .map(|svg| {
+            if svg.contains("xmlns=") {
+                svg
+            } else {
+                svg.replacen("<svg", "<svg xmlns=\"http://www.w3.org/2000/svg\"", 1)
+            }
+        });
 
-    async fn process_yandex_page(id: u32, url: &str) -> Result<Option<LogoJob>, Box<dyn Error>> {
-        println!("Не обработываем yandex страницы {url}");
-        Ok(Some(LogoJob::new(id, url.to_string())))
-    }
-    async fn process_hh_page(id: u32, url: &str) -> Result<Option<LogoJob>, Box<dyn Error>> {
-        println!("Не обрабатываем  hh страницы {url}");
-        Ok(Some(LogoJob::new(id, url.to_string())))
+        PageIcons {
+            rule_icons,
+            icons,
+            manifest_url,
+            inline_svg,
+        }
     }
+}
 
-    fn from_str(url: &str) -> Self {
-        // Регулярное выражение создается один раз
-        static IMAGE_REGEX: Lazy<Regex> =
-            Lazy::new(|| Regex::new(r"(?i)\.(jpg|jpeg|png|gif|webp|svg|ico)(\?|$|/)").unwrap());
-
-        // Константные паттерны для поиска
-        const PATTERNS: &[(&str, fn(String) -> UrlType)] = &[
-            ("https://vk.com/", UrlType::VK),
-            ("https://t.me/", UrlType::Telegram),
-            ("https://www.rustore.ru/", UrlType::RuStore),
-            ("https://apps.apple.com/", UrlType::AppleApp),
-            ("https://appgallery.huawei.com/", UrlType::AppGallery),
-            ("https://play.google.com/store/apps/", UrlType::GooglePlay),
-            ("https://yandex.ru/maps/", UrlType::YandexMap),
-        ];
-
-        // Если есть картинка
-        if IMAGE_REGEX.is_match(&url.to_lowercase()) {
-            return UrlType::ImageUrl(url.to_string());
-        }
+/// Сохранение встроенного SVG страницы в файл `<inline_svg_folder>/<id>.svg`
+async fn save_inline_svg(
+    config: &Config,
+    id: u32,
+    svg: &str,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let folder = config.inline_svg_folder();
+    tokio::fs::create_dir_all(&folder).await?;
+    let path = folder.join(format!("{id}.svg"));
+    tokio::fs::write(&path, svg).await?;
+    Ok(path)
+}
 
-        // Типизируем по url
-        for (pattern, constructor) in PATTERNS {
-            if url.starts_with(pattern) {
-                return constructor(url.to_string());
+/// Поиск полей `logo` (строка, объект с `url` или массив) в schema.org JSON-LD
+fn json_ld_logos(value: &serde_json::Value, logos: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Array(items) => items.iter().for_each(|item| json_ld_logos(item, logos)),
+        serde_json::Value::Object(map) => {
+            for (key, item) in map {
+                match (key.as_str(), item) {
+                    ("logo", serde_json::Value::String(url)) => logos.push(url.clone()),
+                    ("logo", serde_json::Value::Object(logo)) => {
+                        if let Some(url) = logo
+                            .get("url")
+                            .or_else(|| logo.get("contentUrl"))
+                            .and_then(|u| u.as_str())
+                        {
+                            logos.push(url.to_string());
+                        }
+                    }
+                    _ => json_ld_logos(item, logos),
+                }
             }
         }
+        _ => {}
+    }
+}
 
-        // HH: `https://spb.hh.ru/...` и другие поддомены не матчатся через starts_with паттерн
-        if let Ok(parsed) = Url::parse(url) {
-            if parsed
-                .host_str()
-                .is_some_and(|host| host.eq_ignore_ascii_case("hh.ru") || host.ends_with(".hh.ru"))
-            {
-                return UrlType::HH(url.to_string());
-            }
-        }
+/// Иконки из манифеста веб-приложения (`icons: [{src, sizes, type}]`)
+fn manifest_icons(manifest: &str, manifest_url: &Url) -> Vec<IconInfo> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(manifest) else {
+        return Vec::new();
+    };
 
-        UrlType::Other(url.to_string())
-    }
+    json["icons"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|icon| {
+            let src = join_http_url(manifest_url, icon["src"].as_str()?)?;
+            Some(IconInfo {
+                url: src.to_string(),
+                icon_type: "manifest".to_string(),
+                sizes: icon["sizes"].as_str().map(String::from),
+                mime_type: icon["type"].as_str().map(String::from),
+                priority: 8,
+            })
+        })
+        .collect()
 }
 
+/// Первый кандидат в порядке `rank_icons`
 fn select_best_icon(icons: &[IconInfo]) -> Option<&IconInfo> {
-    icons.iter().max_by(|a, b| {
-        // Сначала по приоритету
-        match b.priority.cmp(&a.priority) {
-            std::cmp::Ordering::Equal => {
-                // Затем по размеру (больше = лучше)
-                let size_a = a.sizes.as_ref().and_then(|s| parse_size(s)).unwrap_or(0);
-                let size_b = b.sizes.as_ref().and_then(|s| parse_size(s)).unwrap_or(0);
-                size_b.cmp(&size_a)
-            }
-            other => other,
-        }
-    })
+    rank_icons(icons).into_iter().next()
+}
+
+/// Ссылки кандидатов со страницы сайта в порядке выбора, без манифеста и известных путей
+pub fn rank_page_icons(html: &str, base_url: &Url) -> Vec<String> {
+    let page = PageIcons::collect(html, base_url, &SiteRules::default());
+    rank_icons(&page.icons)
+        .into_iter()
+        .map(|icon| icon.url.clone())
+        .collect()
 }
 
-fn parse_size(sizes: &str) -> Option<u32> {
-    // Парсит строку размеров типа "32x32" или "32x32 64x64"
+/// Кандидаты в порядке выбора: сначала объявленные векторные, затем по убыванию оценки
+fn rank_icons(icons: &[IconInfo]) -> Vec<&IconInfo> {
+    let mut ranked: Vec<&IconInfo> = icons.iter().collect();
+    // Стабильная сортировка сохраняет порядок на странице при равной оценке
+    ranked.sort_by_key(|icon| std::cmp::Reverse((icon.is_preferred_vector(), icon.score())));
+    ranked
+}
+
+/// Размер (ширина, высота) из строки типа "32x32", "16x16 32x32" или "any" у векторных иконок.
+/// Из нескольких размеров выбирается наибольший
+fn parse_size(sizes: &str) -> Option<(u32, u32)> {
     sizes
         .split_whitespace()
         .filter_map(|size| {
-            let parts: Vec<&str> = size.split('x').collect();
-            if parts.len() == 2 {
-                parts[0].parse::<u32>().ok()
-            } else {
-                None
+            if size.eq_ignore_ascii_case("any") {
+                return Some((MAX_SCORED_SIZE, MAX_SCORED_SIZE));
             }
+            let size = size.to_lowercase();
+            let (w, h) = size.split_once('x')?;
+            Some((w.parse().ok()?, h.parse().ok()?))
         })
-        .max()
+        .max_by_key(|&(w, h)| w.min(h))
+}
+
+/// Скачивает лучших кандидатов и пересчитывает оценку по реальным размерам картинок.
+/// Векторные и встроенные в data: ссылку кандидаты не скачиваются, недоступные отбрасываются
+async fn probe_icons(client: &Client, icons: Vec<&IconInfo>) -> Vec<IconInfo> {
+    let probes = icons.into_iter().map(|icon| async move {
+        if icon.is_vector() || icon.url.starts_with("data:") {
+            return Some(icon.clone());
+        }
+        match fetch_bytes(client, &icon.url)
+            .await
+            .and_then(|bytes| image_dimensions(&bytes))
+        {
+            Ok((w, h)) => Some(IconInfo {
+                sizes: Some(format!("{w}x{h}")),
+                ..icon.clone()
+            }),
+            Err(e) => {
+                log::info!("Кандидат {} отброшен при проверке: {e}", icon.url);
+                None
+            }
+        }
+    });
+
+    futures::future::join_all(probes)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn image_dimensions(bytes: &[u8]) -> Result<(u32, u32), Box<dyn Error + Send + Sync>> {
+    Ok(image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()?
+        .into_dimensions()?)
 }
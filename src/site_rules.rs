@@ -0,0 +1,180 @@
+use crate::config::Config;
+use crate::page_extractors::join_http_url;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+// Приоритет правила без явного значения: выбор между несколькими правилами одного сайта
+const DEFAULT_RULE_PRIORITY: u8 = 12;
+
+// Загруженные правила по пути файла: у разных конфигураций могут быть свои файлы
+static SITE_RULES: Lazy<Mutex<HashMap<PathBuf, Arc<SiteRules>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Правила извлечения логотипов для отдельных сайтов из TOML файла.
+/// Позволяют добавить поддержку сайта без пересборки:
+///
+/// ```toml
+/// [[rule]]
+/// host = "example.com"          # домен и его поддомены
+/// selector = "img.brand-logo"   # CSS селектор элемента с логотипом
+/// attr = "src"                  # атрибут со ссылкой, по умолчанию src
+/// rewrite = "_small\\."         # необязательная замена в найденной ссылке
+/// replace = "_large."
+/// priority = 12               # порядок между правилами одного сайта
+/// ```
+#[derive(Debug, Default)]
+pub struct SiteRules {
+    rules: Vec<SiteRule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SiteRulesFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<SiteRuleEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SiteRuleEntry {
+    host: String,
+    selector: String,
+    attr: Option<String>,
+    rewrite: Option<String>,
+    #[serde(default)]
+    replace: String,
+    priority: Option<u8>,
+}
+
+#[derive(Debug)]
+struct SiteRule {
+    host: String,
+    selector: String,
+    attr: String,
+    rewrite: Option<(Regex, String)>,
+    priority: u8,
+}
+
+/// Ссылка на логотип, найденная правилом сайта
+#[derive(Debug)]
+pub struct RuleMatch {
+    pub url: String,
+    pub priority: u8,
+}
+
+/// Правила сайтов из файла конфигурации. Каждый файл читается один раз за запуск
+pub fn site_rules(config: &Config) -> Arc<SiteRules> {
+    let path = config.rules_file();
+    let mut cache = SITE_RULES.lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .entry(path)
+        .or_insert_with_key(|path| Arc::new(SiteRules::load(path)))
+        .clone()
+}
+
+impl SiteRules {
+    /// Загрузка правил. Отсутствующий файл — нет правил, ошибочные правила пропускаются
+    pub fn load(path: &Path) -> SiteRules {
+        if !path.exists() {
+            return SiteRules::default();
+        }
+
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                log::error!("Не удалось прочитать файл правил {}: {e}", path.display());
+                return SiteRules::default();
+            }
+        };
+
+        match Self::parse(&content) {
+            Ok(rules) => {
+                println!(
+                    "Загружено правил сайтов: {} из {}",
+                    rules.rules.len(),
+                    path.display()
+                );
+                rules
+            }
+            Err(e) => {
+                log::error!("Ошибка парсинга файла правил {}: {e}", path.display());
+                SiteRules::default()
+            }
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<SiteRules, toml::de::Error> {
+        let file: SiteRulesFile = toml::from_str(content)?;
+
+        let rules = file
+            .rules
+            .into_iter()
+            .filter_map(|entry| {
+                if let Err(e) = Selector::parse(&entry.selector) {
+                    log::error!(
+                        "Правило {}: некорректный селектор '{}': {e}",
+                        entry.host,
+                        entry.selector
+                    );
+                    return None;
+                }
+                let rewrite = match entry.rewrite.as_deref().map(Regex::new).transpose() {
+                    Ok(rewrite) => rewrite.map(|regex| (regex, entry.replace)),
+                    Err(e) => {
+                        log::error!("Правило {}: некорректная замена: {e}", entry.host);
+                        return None;
+                    }
+                };
+                Some(SiteRule {
+                    host: entry.host.trim_start_matches("*.").to_lowercase(),
+                    selector: entry.selector,
+                    attr: entry.attr.unwrap_or_else(|| "src".to_string()),
+                    rewrite,
+                    priority: entry.priority.unwrap_or(DEFAULT_RULE_PRIORITY),
+                })
+            })
+            .collect();
+
+        Ok(SiteRules { rules })
+    }
+
+    /// Есть ли правило для сайта по ссылке
+    pub fn matches(&self, url: &str) -> bool {
+        Url::parse(url).is_ok_and(|url| self.for_host(&url).next().is_some())
+    }
+
+    /// Ссылки на логотип по правилам сайта страницы
+    pub fn extract(&self, document: &Html, base_url: &Url) -> Vec<RuleMatch> {
+        self.for_host(base_url)
+            .filter_map(|rule| {
+                let selector = Selector::parse(&rule.selector).ok()?;
+                let value = document
+                    .select(&selector)
+                    .find_map(|el| el.value().attr(&rule.attr))?;
+                let url = join_http_url(base_url, value)?.to_string();
+                let url = match &rule.rewrite {
+                    Some((regex, replace)) => regex.replace(&url, replace.as_str()).into_owned(),
+                    None => url,
+                };
+                // Замена по правилу тоже не должна уводить на другую схему
+                let url = join_http_url(base_url, &url)?.to_string();
+                Some(RuleMatch {
+                    url,
+                    priority: rule.priority,
+                })
+            })
+            .collect()
+    }
+
+    fn for_host<'a>(&'a self, url: &Url) -> impl Iterator<Item = &'a SiteRule> {
+        let host = url.host_str().unwrap_or_default().to_lowercase();
+        self.rules
+            .iter()
+            .filter(move |rule| host == rule.host || host.ends_with(&format!(".{}", rule.host)))
+    }
+}
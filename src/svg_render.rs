@@ -0,0 +1,180 @@
+use image::{Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use resvg::tiny_skia::{self, Pixmap, Transform};
+use resvg::usvg::{fontdb, roxmltree, Group, Node, Options, Paint, Size, Tree};
+use std::error::Error;
+use std::sync::Arc;
+
+// Длинная сторона растра SVG. Больше порога обрезки краёв,
+// поэтому отрисованные логотипы не уходят в апскейл
+const RASTER_SIZE: f32 = 1024.0;
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+// Допуск при сравнении фонового прямоугольника с областью SVG, доля размера
+const BACKGROUND_TOLERANCE: f32 = 0.01;
+// Элементы, которые рисуются сами по себе
+const DRAWABLE_ELEMENTS: &[&str] = &[
+    "rect", "path", "circle", "ellipse", "line", "polyline", "polygon", "text", "image", "use",
+];
+// Содержимое этих элементов рисуется только по ссылке
+const NOT_RENDERED_ELEMENTS: &[&str] = &[
+    "defs",
+    "clipPath",
+    "mask",
+    "pattern",
+    "symbol",
+    "marker",
+    "linearGradient",
+    "radialGradient",
+    "filter",
+];
+
+// Системные шрифты для текста в SVG загружаются один раз за запуск
+static FONTS: Lazy<Arc<fontdb::Database>> = Lazy::new(|| {
+    let mut fonts = fontdb::Database::new();
+    fonts.load_system_fonts();
+    Arc::new(fonts)
+});
+
+/// Разбор SVG (в том числе svgz) в дерево usvg: стили, `use` и текст раскрыты в пути
+pub fn parse_svg(bytes: &[u8]) -> Result<Tree, Box<dyn Error + Send + Sync>> {
+    let options = Options {
+        fontdb: FONTS.clone(),
+        ..Options::default()
+    };
+    Tree::from_data(bytes, &options).map_err(|e| format!("Ошибка разбора SVG: {e}").into())
+}
+
+/// Отрисовка SVG на прозрачном фоне, длинная сторона RASTER_SIZE
+pub fn rasterize_svg(tree: &Tree) -> Result<RgbaImage, Box<dyn Error + Send + Sync>> {
+    let size = tree.size();
+    let scale = RASTER_SIZE / size.width().max(size.height());
+    let width = (size.width() * scale).round().max(1.0) as u32;
+    let height = (size.height() * scale).round().max(1.0) as u32;
+
+    let mut pixmap = Pixmap::new(width, height).ok_or("Некорректный размер SVG")?;
+    resvg::render(
+        tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    // tiny-skia хранит цвета с предумноженной альфой
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    RgbaImage::from_raw(width, height, pixels).ok_or_else(|| "Ошибка отрисовки SVG".into())
+}
+
+/// У SVG нет собственного фона: все углы прозрачные
+pub fn has_transparent_background(image: &RgbaImage) -> bool {
+    let (right, bottom) = (image.width() - 1, image.height() - 1);
+    [(0, 0), (right, 0), (0, bottom), (right, bottom)]
+        .into_iter()
+        .all(|(x, y)| image.get_pixel(x, y)[3] == 0)
+}
+
+/// Наложение на белый фон: цвет фона ищется по RGB без учёта прозрачности,
+/// и прозрачные пиксели иначе считались бы чёрными
+pub fn flatten_on_white(image: &RgbaImage) -> RgbaImage {
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let Rgba([r, g, b, a]) = *image.get_pixel(x, y);
+        let blend = |channel: u8| -> u8 {
+            ((channel as u32 * a as u32 + WHITE[0] as u32 * (255 - a as u32)) / 255) as u8
+        };
+        Rgba([blend(r), blend(g), blend(b), WHITE[3]])
+    })
+}
+
+/// Исходный SVG без фонового прямоугольника во всю область: фон итогового логотипа
+/// рисуется отдельно. `None`, если первым рисуется не фон
+pub fn strip_background(bytes: &[u8], tree: &Tree) -> Option<Tree> {
+    match first_drawn(tree.root())? {
+        Node::Path(path) if is_background(path, tree.size()) => {}
+        _ => return None,
+    }
+
+    // Дерево usvg неизменяемое, поэтому фон удаляется из исходного текста
+    let text = std::str::from_utf8(bytes).ok()?;
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..roxmltree::ParsingOptions::default()
+    };
+    let document = roxmltree::Document::parse_with_options(text, options).ok()?;
+    let element = document.root_element().descendants().find(|node| {
+        node.is_element()
+            && DRAWABLE_ELEMENTS.contains(&node.tag_name().name())
+            && !node
+                .ancestors()
+                .any(|parent| NOT_RENDERED_ELEMENTS.contains(&parent.tag_name().name()))
+    })?;
+    if !matches!(element.tag_name().name(), "rect" | "path") {
+        return None;
+    }
+
+    let mut stripped = text.to_string();
+    stripped.replace_range(element.range(), "");
+    let stripped = parse_svg(stripped.as_bytes()).ok()?;
+
+    // Найденный в тексте элемент мог не рисоваться (`display="none"`) или рисоваться
+    // несколько раз через `<use>`: тогда удалён не тот фон, и вся остальная отрисовка
+    // должна остаться прежней, без одного первого элемента
+    let drawn = drawn_bounds(tree.root());
+    (drawn_bounds(stripped.root()) == drawn[1..]).then_some(stripped)
+}
+
+/// Первый рисуемый элемент дерева
+fn first_drawn(group: &Group) -> Option<&Node> {
+    group.children().iter().find_map(|node| match node {
+        Node::Group(group) => first_drawn(group),
+        node => Some(node),
+    })
+}
+
+/// Границы рисуемых элементов дерева в порядке отрисовки
+fn drawn_bounds(group: &Group) -> Vec<tiny_skia::Rect> {
+    group
+        .children()
+        .iter()
+        .flat_map(|node| match node {
+            Node::Group(group) => drawn_bounds(group),
+            node => vec![node.abs_bounding_box()],
+        })
+        .collect()
+}
+
+/// Непрозрачный прямоугольник одного цвета во всю область SVG
+fn is_background(path: &resvg::usvg::Path, size: Size) -> bool {
+    let (tolerance_x, tolerance_y) = (
+        size.width() * BACKGROUND_TOLERANCE,
+        size.height() * BACKGROUND_TOLERANCE,
+    );
+    let bounds = path.abs_bounding_box();
+    let covers = bounds.left() <= tolerance_x
+        && bounds.top() <= tolerance_y
+        && bounds.right() >= size.width() - tolerance_x
+        && bounds.bottom() >= size.height() - tolerance_y;
+    let solid = path
+        .fill()
+        .is_some_and(|fill| matches!(fill.paint(), Paint::Color(_)) && fill.opacity().get() >= 1.0);
+
+    path.is_visible()
+        && solid
+        && covers
+        && !path.abs_transform().has_skew()
+        && is_rectangle(path.data())
+}
+
+/// Все точки контура лежат на сторонах его границ
+fn is_rectangle(data: &tiny_skia::Path) -> bool {
+    let bounds = data.bounds();
+    let near = |a: f32, b: f32| (a - b).abs() <= 1e-3 * (1.0 + b.abs());
+    data.points().iter().all(|point| {
+        (near(point.x, bounds.left()) || near(point.x, bounds.right()))
+            && (near(point.y, bounds.top()) || near(point.y, bounds.bottom()))
+    })
+}
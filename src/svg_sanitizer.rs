@@ -0,0 +1,488 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use resvg::usvg::roxmltree::{self, Node};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::Write;
+
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+// Ограничение размера дерева для файлов с произвольных сайтов
+const MAX_NODES: u32 = 200_000;
+// Наибольшая длина значения сущности DTD. Illustrator объявляет так пространства имён
+const MAX_ENTITY_LENGTH: usize = 128;
+
+// Элементы, которые остаются в SVG. Всё остальное (script, foreignObject, анимации,
+// чужие пространства имён) удаляется вместе с содержимым
+const ALLOWED_ELEMENTS: &[&str] = &[
+    "svg",
+    "g",
+    "defs",
+    "symbol",
+    "use",
+    "title",
+    "desc",
+    "style",
+    "path",
+    "rect",
+    "circle",
+    "ellipse",
+    "line",
+    "polyline",
+    "polygon",
+    "text",
+    "tspan",
+    "textPath",
+    "image",
+    "linearGradient",
+    "radialGradient",
+    "stop",
+    "pattern",
+    "clipPath",
+    "mask",
+    "marker",
+    "filter",
+    "feBlend",
+    "feColorMatrix",
+    "feComponentTransfer",
+    "feComposite",
+    "feDropShadow",
+    "feFlood",
+    "feFuncA",
+    "feFuncB",
+    "feFuncG",
+    "feFuncR",
+    "feGaussianBlur",
+    "feMerge",
+    "feMergeNode",
+    "feMorphology",
+    "feOffset",
+];
+// Ссылка заменяется группой, содержимое остаётся
+const UNWRAPPED_ELEMENTS: &[&str] = &["a", "switch"];
+// Элементы, в которых текст является содержимым
+const TEXT_ELEMENTS: &[&str] = &["title", "desc", "style", "text", "tspan", "textPath"];
+
+const ALLOWED_ATTRIBUTES: &[&str] = &[
+    "id",
+    "class",
+    "style",
+    "version",
+    "viewBox",
+    "preserveAspectRatio",
+    "transform",
+    "x",
+    "y",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "fx",
+    "fy",
+    "fr",
+    "width",
+    "height",
+    "d",
+    "points",
+    "pathLength",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "stroke",
+    "stroke-width",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-opacity",
+    "opacity",
+    "color",
+    "display",
+    "visibility",
+    "overflow",
+    "clip-path",
+    "clip-rule",
+    "mask",
+    "filter",
+    "mix-blend-mode",
+    "isolation",
+    "paint-order",
+    "vector-effect",
+    "shape-rendering",
+    "image-rendering",
+    "color-interpolation",
+    "color-interpolation-filters",
+    "offset",
+    "stop-color",
+    "stop-opacity",
+    "gradientUnits",
+    "gradientTransform",
+    "spreadMethod",
+    "patternUnits",
+    "patternContentUnits",
+    "patternTransform",
+    "clipPathUnits",
+    "maskUnits",
+    "maskContentUnits",
+    "filterUnits",
+    "primitiveUnits",
+    "markerWidth",
+    "markerHeight",
+    "markerUnits",
+    "refX",
+    "refY",
+    "orient",
+    "marker-start",
+    "marker-mid",
+    "marker-end",
+    "font-family",
+    "font-size",
+    "font-weight",
+    "font-style",
+    "font-variant",
+    "font-stretch",
+    "text-anchor",
+    "text-decoration",
+    "dominant-baseline",
+    "alignment-baseline",
+    "baseline-shift",
+    "letter-spacing",
+    "word-spacing",
+    "writing-mode",
+    "dx",
+    "dy",
+    "rotate",
+    "textLength",
+    "lengthAdjust",
+    "startOffset",
+    "href",
+    "in",
+    "in2",
+    "result",
+    "stdDeviation",
+    "mode",
+    "operator",
+    "k1",
+    "k2",
+    "k3",
+    "k4",
+    "type",
+    "values",
+    "tableValues",
+    "slope",
+    "intercept",
+    "amplitude",
+    "exponent",
+    "radius",
+    "flood-color",
+    "flood-opacity",
+];
+// Встроенные растровые картинки, которые можно оставить в <image>
+const ALLOWED_DATA_IMAGES: &[&str] = &[
+    "data:image/png;",
+    "data:image/jpeg;",
+    "data:image/jpg;",
+    "data:image/gif;",
+    "data:image/webp;",
+];
+
+static ENTITY_DECLARATION_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<!ENTITY").unwrap());
+// Простая внутренняя сущность: `<!ENTITY name "значение">`
+static SIMPLE_ENTITY_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<!ENTITY\s+[^\s%"'<>]+\s+(?:"([^"]*)"|'([^']*)')\s*>"#).unwrap());
+static CSS_URL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)url\(\s*['"]?([^'")]*)['"]?\s*\)"#).unwrap());
+// image-set() и -webkit-image-set() загружают картинки по строкам без url()
+static CSS_IMAGE_SET_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)image-set\s*\(").unwrap());
+// Комментарий CSS, незакрытый — до конца таблицы
+static CSS_COMMENT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)/\*.*?(?:\*/|$)").unwrap());
+// Правило без вложенных блоков: `селектор { объявления }`
+static CSS_RULE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"([^{}]+)\{([^{}]*)\}").unwrap());
+// Простой составной селектор: `path`, `.st0`, `g#logo.cls-1`
+static CSS_COMPOUND_SELECTOR_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([A-Za-z][\w-]*)?((?:[.#][\w-]+)*)$").unwrap());
+// Исполняемые конструкции в CSS и значениях атрибутов
+static SCRIPT_VALUE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)javascript\s*:|expression\s*\(|behavior\s*:|-moz-binding").unwrap()
+});
+
+/// Очистка SVG из непроверенного источника по белому списку элементов и атрибутов.
+/// Удаляются скрипты, обработчики событий, `foreignObject`, анимации и внешние ссылки.
+/// Сущности DTD раскрываются при разборе и в результат не попадают
+pub fn sanitize_svg(svg: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    check_entities(svg)?;
+
+    let options = roxmltree::ParsingOptions {
+        // Внешний DOCTYPE без сущностей часто оставляют редакторы
+        allow_dtd: true,
+        nodes_limit: MAX_NODES,
+    };
+    let document = roxmltree::Document::parse_with_options(svg, options)
+        .map_err(|e| format!("Ошибка разбора SVG: {e}"))?;
+    let root = document.root_element();
+    // SVG без xmlns встречается в файлах, вырезанных из HTML страниц
+    let namespace = root.tag_name().namespace();
+    if root.tag_name().name() != "svg" || !matches!(namespace, None | Some(SVG_NAMESPACE)) {
+        return Err("Корневой элемент не svg".into());
+    }
+
+    let names = DocumentNames::collect(&document);
+    let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    write_element(&mut output, root, namespace, &names);
+    Ok(output)
+}
+
+/// Классы и идентификаторы элементов файла, на которые могут ссылаться правила `<style>`
+struct DocumentNames<'a> {
+    classes: HashSet<&'a str>,
+    ids: HashSet<&'a str>,
+}
+
+impl<'a> DocumentNames<'a> {
+    fn collect(document: &'a roxmltree::Document) -> Self {
+        let elements = document.descendants().filter(|node| node.is_element());
+        let mut names = Self {
+            classes: HashSet::new(),
+            ids: HashSet::new(),
+        };
+        for node in elements {
+            names.classes.extend(
+                node.attribute("class")
+                    .unwrap_or_default()
+                    .split_whitespace(),
+            );
+            names.ids.extend(node.attribute("id"));
+        }
+        names
+    }
+}
+
+/// Очистка SVG в байтах, например скачанного файла
+pub fn sanitize_svg_bytes(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let svg = std::str::from_utf8(bytes).map_err(|_| "SVG не в кодировке UTF-8")?;
+    Ok(sanitize_svg(svg)?.into_bytes())
+}
+
+/// Допускаются только короткие сущности без вложенных ссылок и разметки:
+/// вложенные раскрываются экспоненциально (billion laughs), внешние читают файлы
+fn check_entities(svg: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let declared = ENTITY_DECLARATION_REGEX.find_iter(svg).count();
+    let simple = SIMPLE_ENTITY_REGEX
+        .captures_iter(svg)
+        .filter(|captures| {
+            let value = captures
+                .get(1)
+                .or(captures.get(2))
+                .map_or("", |m| m.as_str());
+            value.len() <= MAX_ENTITY_LENGTH && !value.contains(['&', '%', '<'])
+        })
+        .count();
+    if simple == declared {
+        Ok(())
+    } else {
+        Err("SVG с недопустимыми сущностями DTD".into())
+    }
+}
+
+fn write_element(output: &mut String, node: Node, namespace: Option<&str>, names: &DocumentNames) {
+    let is_root = node.parent_element().is_none();
+    let element = node.tag_name().name();
+    let element = if UNWRAPPED_ELEMENTS.contains(&element) {
+        "g"
+    } else {
+        element
+    };
+
+    output.push('<');
+    output.push_str(element);
+    if is_root {
+        let _ = write!(
+            output,
+            r#" xmlns="{SVG_NAMESPACE}" xmlns:xlink="{XLINK_NAMESPACE}""#
+        );
+    }
+    for attribute in node.attributes() {
+        if let Some((name, value)) = sanitize_attribute(element, &attribute) {
+            let _ = write!(output, r#" {name}="{}""#, escape(&value));
+        }
+    }
+
+    let children: Vec<Node> = node
+        .children()
+        .filter(|child| is_allowed(*child, element, namespace))
+        .collect();
+    if children.is_empty() {
+        output.push_str("/>");
+        return;
+    }
+
+    output.push('>');
+    for child in children {
+        if child.is_element() {
+            write_element(output, child, namespace, names);
+        } else if child.is_comment() {
+            let _ = write!(output, "<!--{}-->", child.text().unwrap_or_default());
+        } else if let Some(text) = child.text() {
+            let text = if element == "style" {
+                sanitize_css(text, names)
+            } else {
+                text.to_string()
+            };
+            output.push_str(&escape(&text));
+        }
+    }
+    let _ = write!(output, "</{element}>");
+}
+
+/// Дочерний узел остаётся: разрешённый элемент SVG, комментарий или текст.
+/// Вне текстовых элементов сохраняются только пробелы форматирования
+fn is_allowed(node: Node, parent: &str, namespace: Option<&str>) -> bool {
+    if node.is_element() {
+        let name = node.tag_name().name();
+        node.tag_name().namespace() == namespace
+            && (ALLOWED_ELEMENTS.contains(&name) || UNWRAPPED_ELEMENTS.contains(&name))
+    } else if node.is_text() {
+        TEXT_ELEMENTS.contains(&parent) || node.text().unwrap_or_default().trim().is_empty()
+    } else {
+        // Комментарий, оканчивающийся на `-`, при записи дал бы некорректный `--->`
+        node.is_comment() && !node.text().unwrap_or_default().ends_with('-')
+    }
+}
+
+/// Имя и значение атрибута для записи или `None`, если атрибут удаляется
+fn sanitize_attribute(element: &str, attribute: &roxmltree::Attribute) -> Option<(String, String)> {
+    let name = attribute.name();
+    let value = attribute.value();
+
+    match attribute.namespace() {
+        None => {}
+        Some(XLINK_NAMESPACE) if name == "href" => {}
+        Some(XML_NAMESPACE) if name == "space" => {
+            return Some(("xml:space".to_string(), value.to_string()))
+        }
+        Some(_) => return None,
+    }
+    if !ALLOWED_ATTRIBUTES.contains(&name) || SCRIPT_VALUE_REGEX.is_match(value) {
+        return None;
+    }
+
+    match name {
+        "href" => {
+            is_allowed_href(element, value).then(|| ("xlink:href".to_string(), value.to_string()))
+        }
+        "style" => Some((name.to_string(), sanitize_style(value))),
+        // url(...) в атрибутах оформления допустим только на элементы этого же файла
+        _ if is_unsafe_css_value(value) => None,
+        _ => Some((name.to_string(), value.to_string())),
+    }
+}
+
+/// Ссылки только внутри файла; в `<image>` ещё встроенные растровые картинки
+fn is_allowed_href(element: &str, href: &str) -> bool {
+    let href = href.trim();
+    let lowercase = href.to_lowercase();
+    href.starts_with('#')
+        || (element == "image"
+            && ALLOWED_DATA_IMAGES
+                .iter()
+                .any(|prefix| lowercase.starts_with(prefix)))
+}
+
+/// Значение CSS, которое может загрузить внешний ресурс или выполнить код.
+/// Экранирование (`u\72l(`, `@\69mport`) не раскрывается, а запрещается целиком
+fn is_unsafe_css_value(value: &str) -> bool {
+    value.contains('\\')
+        || SCRIPT_VALUE_REGEX.is_match(value)
+        || CSS_IMAGE_SET_REGEX.is_match(value)
+        || CSS_URL_REGEX
+            .captures_iter(value)
+            .any(|captures| !captures[1].trim().starts_with('#'))
+}
+
+/// Атрибут style: только разрешённые свойства без внешних ссылок
+fn sanitize_style(style: &str) -> String {
+    style
+        .split(';')
+        .filter_map(|declaration| {
+            let (property, value) = declaration.split_once(':')?;
+            let property = property.trim();
+            let allowed = ALLOWED_ATTRIBUTES.contains(&property.to_lowercase().as_str())
+                && !is_unsafe_css_value(property)
+                && !is_unsafe_css_value(value);
+            allowed.then(|| format!("{property}:{}", value.trim()))
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Таблица стилей собирается заново из простых правил с селекторами элементов этого файла.
+/// Комментарии, @-правила (@import, @font-face, @media) и правила для страницы
+/// (`body`, `*`, `:root`, классы не из файла) отбрасываются
+fn sanitize_css(css: &str, names: &DocumentNames) -> String {
+    let css = CSS_COMMENT_REGEX.replace_all(css, "");
+    CSS_RULE_REGEX
+        .captures_iter(&css)
+        .filter_map(|captures| {
+            let selectors = captures[1]
+                .split(',')
+                .map(str::trim)
+                .filter(|selector| is_document_selector(selector, names))
+                .collect::<Vec<_>>()
+                .join(",");
+            let declarations = sanitize_style(&captures[2]);
+            (!selectors.is_empty() && !declarations.is_empty())
+                .then(|| format!("{selectors}{{{declarations};}}"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Селектор из простых частей (`g .st0`, `#logo > path`), который ссылается хотя бы
+/// на один класс или идентификатор этого файла и не задевает остальную страницу
+fn is_document_selector(selector: &str, names: &DocumentNames) -> bool {
+    let mut references_document = false;
+    let valid = selector
+        .split(|c: char| c.is_whitespace() || matches!(c, '>' | '+' | '~'))
+        .filter(|compound| !compound.is_empty())
+        .all(|compound| {
+            let Some(captures) = CSS_COMPOUND_SELECTOR_REGEX.captures(compound) else {
+                return false;
+            };
+            if captures
+                .get(1)
+                .is_some_and(|element| !ALLOWED_ELEMENTS.contains(&element.as_str()))
+            {
+                return false;
+            }
+            // `.a#b.c` -> ".a", "#b", ".c"
+            let simple = &captures[2];
+            simple.match_indices(['.', '#']).all(|(index, marker)| {
+                let name = simple[index + 1..]
+                    .split(['.', '#'])
+                    .next()
+                    .unwrap_or_default();
+                let known = if marker == "." {
+                    names.classes.contains(name)
+                } else {
+                    names.ids.contains(name)
+                };
+                references_document |= known;
+                known
+            })
+        });
+    valid && references_document
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,4 +1,5 @@
 use crate::background_works::DominantColor;
+use crate::svg_sanitizer::sanitize_svg;
 use crate::vectorize;
 use base64::engine::general_purpose::STANDARD as BASE64;
 use base64::Engine;
@@ -6,6 +7,8 @@ use image::{DynamicImage, RgbaImage};
 use log::info;
 use oxipng::{optimize_from_memory, Options};
 use regex::Regex;
+use resvg::tiny_skia::Rect;
+use resvg::usvg::{Group, Node, Tree, WriteOptions};
 use std::error::Error;
 use std::path::Path;
 
@@ -96,6 +99,80 @@ pub fn save_ready_logo(
         )
     };
 
+    write_logo_svg(job_id, &background_color, &logo_svg, output_path)
+}
+
+/// Итоговый логотип из путей исходного SVG без фона: содержимое центрируется
+/// по реальным границам, как и векторизованный растр
+pub fn save_source_vector_logo(
+    tree: &Tree,
+    job_id: u32,
+    background_color: DominantColor,
+    output_path: &Path,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let size = tree.size();
+    let viewport =
+        Rect::from_xywh(0.0, 0.0, size.width(), size.height()).ok_or("Некорректный размер SVG")?;
+    // Вложенный SVG обрезает всё за пределами своей области
+    let bounds = content_bounds(tree.root())
+        .and_then(|bounds| bounds.intersect(&viewport))
+        .ok_or("Исходный SVG пустой")?;
+
+    let transform = LogoTransform::for_size(bounds.width() as f64, bounds.height() as f64);
+    let source_svg = tree.to_string(&WriteOptions {
+        // Чтобы id градиентов и масок не пересекались с id итогового файла
+        id_prefix: Some(format!("logo{job_id}-")),
+        ..WriteOptions::default()
+    });
+    info!(
+        "Для {} выбран исходный SVG {}",
+        output_path.display(),
+        source_svg.len()
+    );
+
+    let logo_svg = format!(
+        r#"<!-- Source SVG -->
+<g transform="translate({offset_x:.2}, {offset_y:.2}) scale({scale:.4}) translate({x:.2}, {y:.2})">
+            {source_svg}
+</g>"#,
+        offset_x = transform.offset_x,
+        offset_y = transform.offset_y,
+        scale = transform.scale,
+        x = -bounds.x(),
+        y = -bounds.y()
+    );
+
+    write_logo_svg(job_id, &background_color, &logo_svg, output_path)
+}
+
+/// Границы нарисованного в SVG. Пустые группы usvg имеют нулевые границы в начале
+/// координат и портят границы родителя, поэтому объединяются только сами элементы
+fn content_bounds(group: &Group) -> Option<Rect> {
+    group
+        .children()
+        .iter()
+        .filter_map(|node| match node {
+            Node::Group(group) => content_bounds(group),
+            node => Some(node.abs_stroke_bounding_box()),
+        })
+        .reduce(|a, b| {
+            Rect::from_ltrb(
+                a.left().min(b.left()),
+                a.top().min(b.top()),
+                a.right().max(b.right()),
+                a.bottom().max(b.bottom()),
+            )
+            .unwrap_or(a)
+        })
+}
+
+/// Итоговый SVG 300x300: фон доминирующего цвета и логотип поверх
+fn write_logo_svg(
+    job_id: u32,
+    background_color: &DominantColor,
+    logo_svg: &str,
+    output_path: &Path,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
     // Создаем SVG
     let svg_file = format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -111,6 +188,9 @@ pub fn save_ready_logo(
         b = background_color.color.blue,
     );
 
+    // Исходные пути SVG с сайта и base64 картинки проверяются и в итоговом файле
+    let svg_file = sanitize_svg(&svg_file)?;
+
     // Сохраняем файл
     info!("Сохраняем файл {}", output_path.display());
     std::fs::write(output_path, svg_file)?;
@@ -127,7 +207,10 @@ struct LogoTransform {
 
 impl LogoTransform {
     fn calculate_transform(image: &RgbaImage) -> LogoTransform {
-        let (w, h) = (image.width() as f64, image.height() as f64);
+        Self::for_size(image.width() as f64, image.height() as f64)
+    }
+
+    fn for_size(w: f64, h: f64) -> LogoTransform {
         let target = WIDTH_HEIGHT as f64;
 
         let scale = (target / w).min(target / h) * LOGO_SCALE_FACTOR;
@@ -0,0 +1,178 @@
+use crate::content_sniff::{sniff_content, ContentKind};
+use crate::page_extractors::{fetch_head, fetch_typed_bytes};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use image::{DynamicImage, ImageFormat};
+use reqwest::{Client, StatusCode};
+use std::error::Error;
+use std::io::Cursor;
+use url::Url;
+
+// Иконки меньше этого размера после увеличения непригодны для логотипа
+const MIN_ICON_SIZE: u32 = 32;
+const ICO_HEADER_SIZE: usize = 6;
+const ICO_ENTRY_SIZE: usize = 16;
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Иконка, найденная по известному пути сайта
+#[derive(Debug)]
+pub struct WellKnownIcon {
+    pub url: String,
+    pub mime_type: String,
+    /// Реальный размер картинки, у SVG отсутствует
+    pub dimensions: Option<(u32, u32)>,
+}
+
+/// Проверка известных путей иконок сайта (`/apple-touch-icon.png`, `/favicon.ico` ...).
+/// Пути проверяются параллельно, порядок результатов совпадает с порядком путей
+pub async fn probe_well_known_icons(
+    client: &Client,
+    base_url: &Url,
+    paths: &[String],
+) -> Vec<WellKnownIcon> {
+    let probes = paths
+        .iter()
+        .filter_map(|path| base_url.join(path).ok())
+        .map(|url| async move {
+            match probe_icon(client, &url).await {
+                Ok(icon) => icon,
+                Err(e) => {
+                    log::info!("Известный путь {url} не подошёл: {e}");
+                    None
+                }
+            }
+        });
+
+    futures::future::join_all(probes)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+async fn probe_icon(
+    client: &Client,
+    url: &Url,
+) -> Result<Option<WellKnownIcon>, Box<dyn Error + Send + Sync>> {
+    // HEAD отсекает отсутствующие пути без скачивания. Не все серверы отвечают на HEAD,
+    // поэтому при других ошибках решение принимается по GET
+    match fetch_head(client, url.as_str()).await {
+        Ok((status, _)) if status == StatusCode::NOT_FOUND || status == StatusCode::GONE => {
+            return Ok(None)
+        }
+        Ok((status, Some(content_type)))
+            if status.is_success() && !is_image_content_type(&content_type) =>
+        {
+            return Ok(None)
+        }
+        _ => {}
+    }
+
+    let (content_type, bytes) = fetch_typed_bytes(client, url.as_str()).await?;
+    if content_type
+        .as_deref()
+        .is_some_and(|content_type| !is_image_content_type(content_type))
+    {
+        // Сайты часто отдают на любой путь HTML страницу со статусом 200
+        return Ok(None);
+    }
+
+    match sniff_content(&bytes) {
+        ContentKind::Svg => Ok(Some(WellKnownIcon {
+            url: url.to_string(),
+            mime_type: "image/svg+xml".to_string(),
+            dimensions: None,
+        })),
+        ContentKind::Raster(ImageFormat::Ico) => {
+            let frame = ico_largest_frame(&bytes).ok_or("в ICO нет кадров")?;
+            let image = image::load_from_memory(&frame)?;
+            if !is_large_enough(image.width(), image.height()) {
+                return Ok(None);
+            }
+            Ok(Some(frame_icon(&image)?))
+        }
+        ContentKind::Raster(format) => {
+            let (width, height) =
+                image::ImageReader::with_format(Cursor::new(&bytes), format).into_dimensions()?;
+            if !is_large_enough(width, height) {
+                return Ok(None);
+            }
+            Ok(Some(WellKnownIcon {
+                url: url.to_string(),
+                mime_type: format.to_mime_type().to_string(),
+                dimensions: Some((width, height)),
+            }))
+        }
+        // Страница ошибки или другие данные вместо картинки
+        _ => Ok(None),
+    }
+}
+
+/// Наибольший кадр ICO в виде самостоятельного файла: PNG как есть,
+/// BMP кадр — ICO из единственного кадра. При равном размере выбирается большая глубина цвета
+fn ico_largest_frame(bytes: &[u8]) -> Option<Vec<u8>> {
+    // Заголовок: reserved = 0, type = 1 (иконка), количество кадров
+    if read_u16(bytes, 0)? != 0 || read_u16(bytes, 2)? != 1 {
+        return None;
+    }
+    let count = read_u16(bytes, 4)? as usize;
+
+    let (entry, _) = (0..count)
+        .filter_map(|index| {
+            let start = ICO_HEADER_SIZE + index * ICO_ENTRY_SIZE;
+            let entry = bytes.get(start..start + ICO_ENTRY_SIZE)?;
+            // Размер 0 в каталоге означает 256
+            let width = if entry[0] == 0 { 256 } else { entry[0] as u32 };
+            let height = if entry[1] == 0 { 256 } else { entry[1] as u32 };
+            let bits = read_u16(entry, 6)?;
+            Some((entry, (width * height, bits)))
+        })
+        .max_by_key(|(_, score)| *score)?;
+
+    let size = read_u32(entry, 8)? as usize;
+    let offset = read_u32(entry, 12)? as usize;
+    let data = bytes.get(offset..offset.checked_add(size)?)?;
+
+    if data.starts_with(PNG_SIGNATURE) {
+        return Some(data.to_vec());
+    }
+
+    // Каталог из одной записи, указывающей на кадр сразу за ним
+    let data_offset = (ICO_HEADER_SIZE + ICO_ENTRY_SIZE) as u32;
+    let mut ico = Vec::with_capacity(ICO_HEADER_SIZE + ICO_ENTRY_SIZE + data.len());
+    ico.extend_from_slice(&bytes[..4]);
+    ico.extend_from_slice(&1u16.to_le_bytes());
+    ico.extend_from_slice(&entry[..12]);
+    ico.extend_from_slice(&data_offset.to_le_bytes());
+    ico.extend_from_slice(data);
+    Some(ico)
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn is_image_content_type(content_type: &str) -> bool {
+    let content_type = content_type.to_lowercase();
+    content_type.starts_with("image/") || content_type.starts_with("application/octet-stream")
+}
+
+fn is_large_enough(width: u32, height: u32) -> bool {
+    width.min(height) >= MIN_ICON_SIZE
+}
+
+/// Кадр ICO передаётся PNG в data: ссылке, чтобы дальше обрабатывался именно он
+fn frame_icon(image: &DynamicImage) -> Result<WellKnownIcon, Box<dyn Error + Send + Sync>> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+
+    Ok(WellKnownIcon {
+        url: format!("data:image/png;base64,{}", BASE64.encode(png)),
+        mime_type: "image/png".to_string(),
+        dimensions: Some((image.width(), image.height())),
+    })
+}
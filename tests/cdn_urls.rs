@@ -0,0 +1,70 @@
+use logoLoader::test_support::upgrade_cdn_url;
+
+#[test]
+fn google_and_yandex_size_suffixes() {
+    assert_eq!(
+        upgrade_cdn_url("https://lh3.googleusercontent.com/abcDEF=w240-h480-rw").as_deref(),
+        Some("https://lh3.googleusercontent.com/abcDEF=s0")
+    );
+    assert_eq!(
+        upgrade_cdn_url("https://avatars.mds.yandex.net/get-altay/1880508/2a0000016e9f0c7a/S")
+            .as_deref(),
+        Some("https://avatars.mds.yandex.net/get-altay/1880508/2a0000016e9f0c7a/XXXL")
+    );
+}
+
+#[test]
+fn unsigned_vk_avatar_crop_params_removed() {
+    assert_eq!(
+        upgrade_cdn_url("https://pp.userapi.com/c851/v851/1a/xyz.jpg?ava=1&crop=0,0,200,200")
+            .as_deref(),
+        Some("https://pp.userapi.com/c851/v851/1a/xyz.jpg")
+    );
+}
+
+#[test]
+fn signed_urls_kept_as_is() {
+    // Подпись VK покрывает size/crop: без них ссылка отвечает ошибкой
+    assert_eq!(
+        upgrade_cdn_url(
+            "https://sun9-21.userapi.com/s/v1/ig2/Ab_cD.jpg?size=200x200&quality=96&crop=0,0,400,400&sign=0f1e2d3c&type=album"
+        ),
+        None
+    );
+    assert_eq!(
+        upgrade_cdn_url(
+            "https://bucket.s3.amazonaws.com/logo-150x150.png?X-Amz-Signature=abc&width=64"
+        ),
+        None
+    );
+}
+
+#[test]
+fn telegram_userpic_and_cloudinary() {
+    assert_eq!(
+        upgrade_cdn_url("https://t.me/i/userpic/160/zerno.jpg").as_deref(),
+        Some("https://t.me/i/userpic/320/zerno.jpg")
+    );
+    assert_eq!(
+        upgrade_cdn_url(
+            "https://res.cloudinary.com/demo/image/upload/w_64,h_64,c_fill/v123/logo.png"
+        )
+        .as_deref(),
+        Some("https://res.cloudinary.com/demo/image/upload/v123/logo.png")
+    );
+}
+
+#[test]
+fn thumbnail_suffix_and_size_params() {
+    assert_eq!(
+        upgrade_cdn_url("https://zerno.ru/wp-content/uploads/2023/05/logo-150x150.png?ver=2")
+            .as_deref(),
+        Some("https://zerno.ru/wp-content/uploads/2023/05/logo.png?ver=2")
+    );
+    assert_eq!(
+        upgrade_cdn_url("https://cdn.zerno.ru/logo.png?w=64&h=64&v=3").as_deref(),
+        Some("https://cdn.zerno.ru/logo.png?v=3")
+    );
+    // Без размера в ссылке нечего увеличивать
+    assert_eq!(upgrade_cdn_url("https://zerno.ru/logo.png"), None);
+}
@@ -0,0 +1,68 @@
+use image::ImageFormat;
+use logoLoader::test_support::{sniff_content, ContentKind};
+use std::path::Path;
+
+#[test]
+fn svg_fixtures_sniffed_as_svg() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/svg");
+    for entry in std::fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(
+            sniff_content(&bytes),
+            ContentKind::Svg,
+            "{}",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn svg_after_long_prolog() {
+    // Комментарий с лицензией длиннее первых 1024 байт
+    let license = "Лицензия шрифта и логотипа. ".repeat(200);
+    let svg = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!-- {license} -->\n\
+         <!DOCTYPE svg PUBLIC \"-//W3C//DTD SVG 1.1//EN\" \
+         \"http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd\" [\n\
+         <!ENTITY ns_graphs \"http://ns.adobe.com/Graphs/1.0/\">\n]>\n\
+         <svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 10 10\"/>"
+    );
+    assert!(svg.len() > 1024);
+    assert_eq!(sniff_content(svg.as_bytes()), ContentKind::Svg);
+}
+
+#[test]
+fn html_with_inline_svg_is_html() {
+    let html = b"<!DOCTYPE html><html><body><svg viewBox=\"0 0 1 1\"/></body></html>";
+    assert_eq!(sniff_content(html), ContentKind::Html);
+    // Комментарий перед страницей не делает её SVG
+    let html = b"<!-- svg --><html><body><svg/></body></html>";
+    assert_eq!(sniff_content(html), ContentKind::Html);
+}
+
+#[test]
+fn unreadable_image_formats_are_unsupported() {
+    let cases: [(&[u8], ImageFormat); 3] = [
+        (
+            b"\x00\x00\x00\x1cftypavif\x00\x00\x00\x00avifmif1miaf",
+            ImageFormat::Avif,
+        ),
+        (b"II*\x00\x08\x00\x00\x00", ImageFormat::Tiff),
+        (
+            b"qoif\x00\x00\x00\x10\x00\x00\x00\x10\x04\x00",
+            ImageFormat::Qoi,
+        ),
+    ];
+    for (bytes, format) in cases {
+        assert_eq!(
+            sniff_content(bytes),
+            ContentKind::UnsupportedImage(format),
+            "{format:?}"
+        );
+    }
+    assert_eq!(
+        sniff_content(b"\x89PNG\r\n\x1a\n0000"),
+        ContentKind::Raster(ImageFormat::Png)
+    );
+}
@@ -0,0 +1,27 @@
+use logoLoader::test_support::{RetryPolicy, RETRY_BASE_DELAY, RETRY_MAX_DELAY};
+
+#[test]
+fn retry_delay_within_backoff_bounds() {
+    let policy = RetryPolicy { retries: 3 };
+    for attempt in 0..12 {
+        let backoff = RETRY_BASE_DELAY
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(RETRY_MAX_DELAY);
+        // Разброс от половины паузы до полной, не больше максимальной
+        for _ in 0..200 {
+            let delay = policy.delay(attempt);
+            assert!(
+                delay >= backoff / 2 && delay <= backoff,
+                "попытка {attempt}: {delay:?} вне [{:?}, {backoff:?}]",
+                backoff / 2
+            );
+        }
+    }
+}
+
+#[test]
+fn retry_delays_are_spread() {
+    let policy = RetryPolicy { retries: 3 };
+    let delays: std::collections::HashSet<_> = (0..50).map(|_| policy.delay(2)).collect();
+    assert!(delays.len() > 1, "паузы без разброса: {delays:?}");
+}
@@ -0,0 +1,43 @@
+use logoLoader::test_support::redact;
+
+#[test]
+fn masks_query_string_secrets() {
+    let line = redact("POST https://host/service/login?username=ivan&password=Pa$$w0rd&next=/");
+    assert_eq!(
+        line,
+        "POST https://host/service/login?username=ivan&password=***&next=/"
+    );
+    assert_eq!(redact("otp?code=123456"), "otp?code=***");
+    assert_eq!(redact("token=abc.def; secret=xyz"), "token=***; secret=***");
+}
+
+#[test]
+fn masks_json_and_debug_fields() {
+    assert_eq!(
+        redact(r#"{"username":"ivan","password":"Pa$$w0rd"}"#),
+        r#"{"username":"ivan","password":"***"}"#
+    );
+    assert_eq!(
+        redact(r#"LoginResult { otp_code: "123456", otpCode: "654321" }"#),
+        r#"LoginResult { otp_code: "***", otpCode: "***" }"#
+    );
+}
+
+#[test]
+fn masks_session_cookies() {
+    assert_eq!(
+        redact("JSESSIONID=0A1B2C3D; Path=/; HttpOnly"),
+        "JSESSIONID=***; Path=/; HttpOnly"
+    );
+    assert_eq!(redact("cookie: SESSION=abc; XSRF-TOKEN=def"), "cookie: ***");
+    assert_eq!(
+        redact("Set-Cookie: SID=42\nnext line"),
+        "Set-Cookie: ***\nnext line"
+    );
+}
+
+#[test]
+fn keeps_plain_messages() {
+    let message = "Загружено 12 заявок, пользователь ivan";
+    assert!(matches!(redact(message), std::borrow::Cow::Borrowed(_)));
+}
@@ -0,0 +1,247 @@
+use logoLoader::test_support::{
+    appstore_app_id, appstore_icon, appstore_lookup_icon, decode_html, google_play_icon,
+    hh_employer_id, hh_employer_link, hh_employer_logo, is_short_link, join_http_url,
+    meta_refresh_target, store_page_icon, telegram_avatar, vk_avatar, yandex_org_logo,
+};
+use std::path::Path;
+use url::Url;
+
+const YANDEX_LOGO: &str = "https://avatars.mds.yandex.net/get-altay/1880508/2a0000016e9f0c7a/XXXL";
+
+fn fixture_bytes(name: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/html")
+        .join(name);
+    std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()))
+}
+
+fn fixture(name: &str) -> String {
+    String::from_utf8(fixture_bytes(name)).unwrap_or_else(|e| panic!("{name}: {e}"))
+}
+
+/// Логотип Яндекс Карт находится по alt="Логотип" только после верного декодирования
+fn yandex_logo(name: &str, content_type: Option<&str>) -> Option<String> {
+    let html = decode_html(&fixture_bytes(name), content_type);
+    assert!(html.contains("Кофейня \"Зерно\""), "{name}: {html}");
+    yandex_org_logo(&html)
+}
+
+#[test]
+fn vk_community_avatar_from_page_data() {
+    // photo_max_orig автора поста и комментатора не должен победить аватар сообщества
+    assert_eq!(
+        vk_avatar(&fixture("vk_com.html")).as_deref(),
+        Some("https://sun9-21.userapi.com/impg/zerno/avatar.jpg?size=800x800&quality=95&sign=1a2b")
+    );
+}
+
+#[test]
+fn vk_mobile_avatar_ignores_members_and_related_groups() {
+    assert_eq!(
+        vk_avatar(&fixture("vk_mobile.html")).as_deref(),
+        Some("https://sun9-40.userapi.com/s/v1/ig2/garage.jpg?size=400x400&quality=96&ava=1")
+    );
+}
+
+#[test]
+fn vk_ru_club_avatar_by_id_without_placeholders() {
+    assert_eq!(
+        vk_avatar(&fixture("vk_ru.html")).as_deref(),
+        Some("https://sun1-2.userapi.com/flowers.jpg?size=200x200")
+    );
+}
+
+#[test]
+fn rustore_icon_of_the_app_not_similar_apps() {
+    let html = fixture("rustore.html");
+    assert_eq!(
+        store_page_icon(&html, "ru.mybank.mobile").as_deref(),
+        Some("https://static.rustore.ru/imgproxy/mybank/icon.png")
+    );
+    // Без записи приложения — og:image страницы, а не иконка похожего приложения
+    assert_eq!(
+        store_page_icon(&html, "ru.unknown.app").as_deref(),
+        Some("https://www.rustore.ru/og/rustore-share.png")
+    );
+}
+
+#[test]
+fn appgallery_icon_from_page_state_and_detail_api() {
+    let expected = Some("https://appimg.dbankcdn.com/application/icon144/mybank.png");
+    assert_eq!(
+        store_page_icon(&fixture("appgallery.html"), "C101184875").as_deref(),
+        expected
+    );
+    assert_eq!(
+        store_page_icon(&fixture("appgallery_detail.json"), "C101184875").as_deref(),
+        expected
+    );
+}
+
+#[test]
+fn google_play_icon_resized_to_512() {
+    assert_eq!(
+        google_play_icon(&fixture("google_play.html")).as_deref(),
+        Some("https://play-lh.googleusercontent.com/mybank-icon=s512")
+    );
+}
+
+#[test]
+fn page_links_only_http() {
+    let base = Url::parse("https://example.com/about/").unwrap();
+    assert_eq!(
+        join_http_url(&base, "/img/logo.svg")
+            .map(String::from)
+            .as_deref(),
+        Some("https://example.com/img/logo.svg")
+    );
+    assert_eq!(
+        join_http_url(&base, "//cdn.example.com/logo.png")
+            .map(String::from)
+            .as_deref(),
+        Some("https://cdn.example.com/logo.png")
+    );
+    // Ссылки на локальные файлы и встроенные данные со страницы не принимаются
+    for href in [
+        "file:///etc/passwd",
+        "data:image/png;base64,AAAA",
+        "javascript:alert(1)",
+    ] {
+        assert_eq!(join_http_url(&base, href), None, "{href}");
+    }
+}
+
+#[test]
+fn decode_windows_1251_from_meta() {
+    for content_type in [None, Some("text/html")] {
+        assert_eq!(
+            yandex_logo("yandex_cp1251.html", content_type).as_deref(),
+            Some(YANDEX_LOGO)
+        );
+    }
+}
+
+#[test]
+fn decode_koi8_r_from_meta_http_equiv() {
+    assert_eq!(
+        yandex_logo("yandex_koi8r.html", Some("text/html")).as_deref(),
+        Some(YANDEX_LOGO)
+    );
+}
+
+#[test]
+fn decode_bom_over_header() {
+    // BOM важнее кодировки из заголовка
+    assert_eq!(
+        yandex_logo("yandex_bom.html", Some("text/html; charset=windows-1251")).as_deref(),
+        Some(YANDEX_LOGO)
+    );
+}
+
+#[test]
+fn decode_ignores_false_utf8_header() {
+    // Сервер заявляет UTF-8 по умолчанию, а страница в windows-1251
+    assert_eq!(
+        yandex_logo("yandex_cp1251.html", Some("text/html; charset=UTF-8")).as_deref(),
+        Some(YANDEX_LOGO)
+    );
+}
+
+#[test]
+fn hh_vacancy_links_to_its_employer() {
+    let link = hh_employer_link(&fixture("hh_vacancy.html")).expect("нет ссылки на работодателя");
+    let employer_url = Url::parse("https://spb.hh.ru/vacancy/101")
+        .unwrap()
+        .join(&link)
+        .unwrap();
+    assert_eq!(hh_employer_id(&employer_url).as_deref(), Some("1740"));
+}
+
+#[test]
+fn hh_original_logo_of_the_employer_not_other_companies() {
+    let html = fixture("hh_employer.html");
+    // Логотип VK из вакансий в данных страницы идёт раньше, но принадлежит другому работодателю
+    assert_eq!(
+        hh_employer_logo(&html, Some("1740")).as_deref(),
+        Some("https://img.hhcdn.ru/employer-logo-original/1164357.png")
+    );
+    // Без идентификатора — только элемент логотипа работодателя
+    assert_eq!(
+        hh_employer_logo(&html, None).as_deref(),
+        Some("https://img.hhcdn.ru/employer-logo/3899160.png")
+    );
+}
+
+#[test]
+fn telegram_avatar_from_preview_page() {
+    // Картинка блока фото без обрезки превью, ссылка без протокола дополняется до https
+    assert_eq!(
+        telegram_avatar(&fixture("telegram_channel.html")).as_deref(),
+        Some("https://cdn4.cdn-telegram.org/file/tKx3wQ2m.jpg")
+    );
+}
+
+#[test]
+fn telegram_default_logo_is_not_an_avatar() {
+    assert_eq!(telegram_avatar(&fixture("telegram_no_avatar.html")), None);
+}
+
+const APPSTORE_ICON: &str = "https://is1-ssl.mzstatic.com/image/thumb/Purple126/v4/3a/7f/c2/3a7fc2d1-0b5e-4e3a-9a1d-6f2c1b7e8d90/AppIcon-0-0-1x_U007emarketing-0-7-0-85-220.png/1024x1024bb.png";
+
+#[test]
+fn appstore_icon_from_artwork_template() {
+    assert_eq!(
+        appstore_app_id("https://apps.apple.com/ru/app/zerno/id1234567890?l=en").as_deref(),
+        Some("1234567890")
+    );
+    // Шаблон иконки, а не обрезанный og:image и не скриншот
+    assert_eq!(
+        appstore_icon(&fixture("appstore.html")).as_deref(),
+        Some(APPSTORE_ICON)
+    );
+}
+
+#[test]
+fn appstore_icon_from_lookup_api() {
+    assert_eq!(
+        appstore_lookup_icon(&fixture("appstore_lookup.json")).as_deref(),
+        Some(APPSTORE_ICON)
+    );
+    assert_eq!(
+        appstore_lookup_icon(r#"{"resultCount":0,"results":[]}"#),
+        None
+    );
+}
+
+#[test]
+fn yandex_logo_from_logo_block() {
+    assert_eq!(
+        yandex_org_logo(&fixture("yandex_logo_class.html")).as_deref(),
+        Some("https://avatars.mds.yandex.net/get-altay/2756107/2a00000174b1c3a9/XXXL")
+    );
+}
+
+#[test]
+fn yandex_photos_and_maps_logo_are_not_an_org_logo() {
+    assert_eq!(yandex_org_logo(&fixture("yandex_no_logo.html")), None);
+}
+
+#[test]
+fn short_link_hosts() {
+    assert!(is_short_link("https://clck.ru/3AbCdE"));
+    assert!(is_short_link("http://BIT.LY/zerno"));
+    assert!(!is_short_link("https://zerno-coffee.ru/clck.ru"));
+    assert!(!is_short_link("https://sub.clck.ru.example.com/"));
+    assert!(!is_short_link("clck.ru/3AbCdE"));
+}
+
+#[test]
+fn meta_refresh_target_from_short_link_page() {
+    // Первый refresh без учёта регистра, адрес в кавычках относительно страницы
+    let base_url = Url::parse("https://clck.ru/3AbCdE").unwrap();
+    assert_eq!(
+        meta_refresh_target(&fixture("short_link_refresh.html"), &base_url).map(String::from),
+        Some("https://clck.ru/redirect?to=zerno".to_string())
+    );
+    assert_eq!(meta_refresh_target(&fixture("vk_ru.html"), &base_url), None);
+}
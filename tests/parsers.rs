@@ -0,0 +1,74 @@
+use logoLoader::test_support::{rank_page_icons, SiteRules, UrlType};
+use std::path::Path;
+use url::Url;
+
+fn ranked(name: &str) -> Vec<String> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/html")
+        .join(name);
+    let html = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    let base_url = Url::parse("https://example.com/").unwrap();
+    rank_page_icons(&html, &base_url)
+}
+
+#[test]
+fn source_priority_beats_size_and_format() {
+    // Объявленный SVG выбирается первым, остальные по приоритету источника:
+    // og:image JPEG без размера выше apple-touch-icon PNG 180x180,
+    // размер и формат решают только внутри одного источника.
+    // Силуэт mask-icon ниже всех растровых иконок, включая ICO
+    assert_eq!(
+        ranked("icons_priority.html"),
+        [
+            "https://example.com/icon.svg",
+            "https://cdn.example.com/og.jpg",
+            "https://example.com/apple-touch-icon.png",
+            "https://example.com/icon-192.png",
+            "https://example.com/icon-32.png",
+            "https://example.com/favicon.ico",
+            "https://example.com/mask.svg",
+        ]
+    );
+}
+
+#[test]
+fn sizes_aspect_and_ties() {
+    assert_eq!(
+        ranked("icons_sizes.html"),
+        [
+            // Квадрат выигрывает у вытянутых картинок той же меньшей стороны
+            "https://example.com/square.png",
+            // Из нескольких размеров берётся наибольший, регистр `X` не важен
+            "https://example.com/multi.png",
+            // При равной оценке сохраняется порядок на странице
+            "https://example.com/first-32.png",
+            "https://example.com/second-32.png",
+            "https://example.com/slight.png",
+            // WebP того же размера ниже PNG
+            "https://example.com/webp-48.webp",
+            // Вытянутая 4:1 картинка последняя, несмотря на большую сторону 512
+            "https://example.com/wide.png",
+        ]
+    );
+}
+
+#[test]
+fn store_links_classified_by_host() {
+    let rules = SiteRules::default();
+    for (url, expected) in [
+        (
+            "https://www.rustore.ru/catalog/app/ru.sberbankmobile",
+            UrlType::RuStore("https://rustore.ru/catalog/app/ru.sberbankmobile".to_string()),
+        ),
+        (
+            "https://apps.rustore.ru/app/ru.sberbankmobile",
+            UrlType::RuStore("https://apps.rustore.ru/app/ru.sberbankmobile".to_string()),
+        ),
+        (
+            "https://apps.apple.com/ru/app/id492224193",
+            UrlType::AppleApp("https://apps.apple.com/ru/app/id492224193".to_string()),
+        ),
+    ] {
+        assert_eq!(UrlType::from_str(url, &rules), expected, "{url}");
+    }
+}
@@ -0,0 +1,34 @@
+use logoLoader::test_support::{parse_svg, strip_background};
+use std::path::Path;
+
+fn stripped(name: &str) -> Option<resvg::usvg::Tree> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/svg")
+        .join(name);
+    let bytes = std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    let tree = parse_svg(&bytes).unwrap_or_else(|e| panic!("{name}: {e}"));
+    strip_background(&bytes, &tree)
+}
+
+#[test]
+fn strips_background_rect() {
+    let tree = stripped("background_rect.svg").expect("фон не удалён");
+    // Остался только круг
+    let bounds = tree.root().abs_bounding_box();
+    assert_eq!(
+        (bounds.left(), bounds.top(), bounds.right(), bounds.bottom()),
+        (20.0, 20.0, 80.0, 80.0)
+    );
+}
+
+#[test]
+fn keeps_svg_when_first_rect_is_hidden() {
+    // Первый прямоугольник в тексте не рисуется, фон — второй
+    assert!(stripped("background_hidden.svg").is_none());
+}
+
+#[test]
+fn keeps_svg_when_background_is_reused() {
+    // Удаление фона убрало бы и его копию через <use>
+    assert!(stripped("background_use.svg").is_none());
+}
@@ -0,0 +1,151 @@
+use logoLoader::test_support::sanitize_svg;
+use std::path::Path;
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/svg")
+        .join(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()))
+}
+
+fn sanitized(name: &str) -> String {
+    let svg = sanitize_svg(&fixture(name)).unwrap_or_else(|e| panic!("{name}: {e}"));
+    // Результат очистки должен оставаться корректным SVG
+    resvg::usvg::Tree::from_str(&svg, &resvg::usvg::Options::default())
+        .unwrap_or_else(|e| panic!("{name}: результат не разбирается: {e}\n{svg}"));
+    svg
+}
+
+fn assert_absent(svg: &str, needles: &[&str]) {
+    let lowercase = svg.to_lowercase();
+    for needle in needles {
+        assert!(
+            !lowercase.contains(&needle.to_lowercase()),
+            "'{needle}' остался в SVG:\n{svg}"
+        );
+    }
+}
+
+#[test]
+fn removes_scripts() {
+    let svg = sanitized("script.svg");
+    assert_absent(&svg, &["<script", "alert", "evil.example", "cookie"]);
+    assert!(svg.contains(r##"fill="#e30613""##));
+}
+
+#[test]
+fn removes_event_handlers() {
+    let svg = sanitized("event_handlers.svg");
+    assert_absent(
+        &svg,
+        &["onload", "onclick", "onmouseover", "onerror", "alert"],
+    );
+    assert!(svg.contains("<rect"));
+    assert!(svg.contains("<circle"));
+}
+
+#[test]
+fn drops_external_references() {
+    let svg = sanitized("external_references.svg");
+    assert_absent(
+        &svg,
+        &[
+            "evil.example",
+            "file:",
+            "javascript:",
+            "data:image/svg+xml",
+            "<a",
+        ],
+    );
+    // Ссылки внутри файла и встроенные растровые картинки остаются
+    assert!(svg.contains(r#"fill="url(#local)""#));
+    assert!(svg.contains("data:image/png;base64,"));
+    assert!(svg.contains("stroke:#000"));
+}
+
+#[test]
+fn removes_foreign_object() {
+    let svg = sanitized("foreign_object.svg");
+    assert_absent(&svg, &["foreignObject", "<body", "iframe", "onload"]);
+    assert!(svg.contains("<circle"));
+}
+
+#[test]
+fn removes_animations() {
+    let svg = sanitized("animation.svg");
+    assert_absent(&svg, &["<animate", "<set", "javascript:", "alert"]);
+    assert!(svg.contains("<circle"));
+}
+
+#[test]
+fn cleans_stylesheets() {
+    let svg = sanitized("stylesheet.svg");
+    assert_absent(&svg, &["@import", "evil.example", "expression("]);
+    assert!(svg.contains(".brand{fill:#e30613;}"));
+}
+
+#[test]
+fn rejects_css_escapes() {
+    // Экранирование обходило поиск `@import` и `url(`
+    let svg = sanitized("css_escapes.svg");
+    assert_absent(&svg, &["evil.example", "\\"]);
+    assert!(svg.contains(".mark{fill:#0057b8;}"));
+    assert!(svg.contains(r#"style="stroke:#000""#));
+}
+
+#[test]
+fn rejects_image_set() {
+    let svg = sanitized("css_image_set.svg");
+    assert_absent(&svg, &["evil.example", "image-set"]);
+    assert!(svg.contains(".mark{stroke:#000;}"));
+    assert!(svg.contains(r#"style="stroke:#000""#));
+}
+
+#[test]
+fn keeps_only_rules_for_svg_elements() {
+    let svg = sanitized("css_page_selectors.svg");
+    assert_absent(
+        &svg,
+        &[
+            "body",
+            "html",
+            "*",
+            ":root",
+            "banner",
+            "@media",
+            "@font-face",
+            "Evil",
+            "a.logo",
+            "[class]",
+        ],
+    );
+    assert!(svg.contains(".logo{fill:#e30613;}"));
+    assert!(svg.contains("#mark path{stroke:#000;}"));
+}
+
+#[test]
+fn rejects_dangerous_entities() {
+    for name in [
+        "billion_laughs.svg",
+        "external_entity.svg",
+        "markup_entity.svg",
+    ] {
+        assert!(sanitize_svg(&fixture(name)).is_err(), "{name} принят");
+    }
+}
+
+#[test]
+fn keeps_editor_exports() {
+    let svg = sanitized("illustrator.svg");
+    assert_absent(&svg, &["<!DOCTYPE", "<!ENTITY", "metadata", "pageBounds"]);
+    assert!(svg.contains(".st0{fill:url(#SVGID_1_);}"));
+    assert!(svg.contains("<linearGradient"));
+    assert!(svg.contains("Логотип &amp; Ко"));
+    assert!(svg.contains(r#"xml:space="preserve""#));
+}
+
+#[test]
+fn is_idempotent() {
+    let once = sanitized("external_references.svg");
+    assert_eq!(sanitize_svg(&once).unwrap(), once);
+}